@@ -0,0 +1,129 @@
+use std::io::{self, BufRead};
+
+use aho_corasick::AhoCorasick;
+
+/// The tokens that can be decoded as a digit
+#[derive(Debug, Clone, Default)]
+pub struct Vocabulary {
+    tokens: Vec<(String, u32)>,
+}
+
+impl Vocabulary {
+    pub fn new() -> Vocabulary {
+        Vocabulary::default()
+    }
+
+    /// The digits from "0" to "9"
+    pub fn digits() -> Vocabulary {
+        Vocabulary::new().with_words(&["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"])
+    }
+
+    /// The english words from "zero" to "nine"
+    pub fn english() -> Vocabulary {
+        Vocabulary::new().with_words(&["zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine"])
+    }
+
+    /// The french words from "zéro" to "neuf"
+    pub fn french() -> Vocabulary {
+        Vocabulary::new().with_words(&["zéro", "un", "deux", "trois", "quatre", "cinq", "six", "sept", "huit", "neuf"])
+    }
+
+    /// Adds a list of words, each word having its index as value
+    pub fn with_words(self, words: &[&str]) -> Vocabulary {
+        words.iter().enumerate().fold(self, |vocabulary, (value, word)| vocabulary.with_token(word, value as u32))
+    }
+
+    pub fn with_token(mut self, token: &str, value: u32) -> Vocabulary {
+        self.tokens.push((token.to_string(), value));
+        self
+    }
+
+    /// Merges two vocabularies
+    pub fn and(mut self, other: Vocabulary) -> Vocabulary {
+        self.tokens.extend(other.tokens);
+        self
+    }
+}
+
+/// Decodes calibration values, matching every token of a vocabulary in a single pass over each line,
+/// overlapping tokens included
+pub struct Decoder {
+    matcher: AhoCorasick,
+    values: Vec<u32>,
+}
+
+impl Decoder {
+    pub fn new(vocabulary: Vocabulary) -> Decoder {
+        let (tokens, values): (Vec<String>, Vec<u32>) = vocabulary.tokens.into_iter().unzip();
+        let matcher = AhoCorasick::new(tokens).expect("Invalid vocabulary");
+        Decoder { matcher, values }
+    }
+
+    /// All the digits found in the line, sorted by starting position
+    pub fn digits(&self, line: &str) -> Vec<u32> {
+        let mut matches: Vec<_> = self.matcher.find_overlapping_iter(line).collect();
+        matches.sort_by_key(|m| (m.start(), std::cmp::Reverse(m.end())));
+        matches.dedup_by_key(|m| m.start());
+        matches.into_iter().map(|m| self.values[m.pattern().as_usize()]).collect()
+    }
+
+    pub fn first(&self, line: &str) -> Option<u32> {
+        self.digits(line).first().copied()
+    }
+
+    pub fn last(&self, line: &str) -> Option<u32> {
+        self.digits(line).last().copied()
+    }
+
+    /// The first and last digits of the line combined as a two digits number
+    pub fn calibration_value(&self, line: &str) -> Option<u32> {
+        let digits = self.digits(line);
+        Some(digits.first()? * 10 + digits.last()?)
+    }
+
+    /// Sums the calibration values of every line, returns None if a line has no digit
+    pub fn sum(&self, input: &str) -> Option<u32> {
+        input.lines().map(|line| self.calibration_value(line)).sum()
+    }
+
+    /// Sums the calibration values of every line of the reader, one line at a time
+    pub fn sum_stream<R: BufRead>(&self, mut reader: R) -> io::Result<u64> {
+        let mut total = 0;
+        let mut line = String::new();
+        while reader.read_line(&mut line)? > 0 {
+            let value = self.calibration_value(line.trim_end()).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("No digit in line: {}", line.trim_end())))?;
+            total += value as u64;
+            line.clear();
+        }
+        Ok(total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overlapping_tokens() {
+        let decoder = Decoder::new(Vocabulary::digits().and(Vocabulary::english()));
+        assert_eq!(decoder.digits("eightwothree"), vec![8, 2, 3]);
+        assert_eq!(decoder.digits("oneight"), vec![1, 8]);
+        assert_eq!(decoder.calibration_value("zoneight234"), Some(14));
+        assert_eq!(decoder.first("abc"), None);
+    }
+
+    #[test]
+    fn test_custom_vocabulary() {
+        let decoder = Decoder::new(Vocabulary::french().with_token("dix", 10).with_token("six", 6));
+        assert_eq!(decoder.digits("deuxhuitroisdix"), vec![2, 8, 3, 10]);
+        assert_eq!(decoder.last("unsixe"), Some(6));
+    }
+
+    #[test]
+    fn test_sum_stream() {
+        let decoder = Decoder::new(Vocabulary::digits().and(Vocabulary::english()));
+        let input = "two1nine\neightwothree\nabcone2threexyz\n";
+        assert_eq!(decoder.sum_stream(input.as_bytes()).unwrap(), 29 + 83 + 13);
+        assert!(decoder.sum_stream("abc\n".as_bytes()).is_err());
+    }
+}
@@ -2,3 +2,4 @@ pub mod part1;
 
 pub mod part2;
 
+pub mod decoder;
@@ -1,10 +1,12 @@
 use std::collections::HashMap;
 
-#[derive(Debug, PartialEq, Eq, Hash)]
-pub enum Color {
-    Red,
-    Blue,
-    Green,
+#[derive(Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+pub struct Color(String);
+
+impl Color {
+    pub fn new(name: &str) -> Color {
+        Color(name.to_string())
+    }
 }
 
 pub struct Game {
@@ -16,6 +18,16 @@ pub struct Set {
     pub(crate) draw: HashMap<Color, u32>,
 }
 
+/// The number of cubes of each color in the bag, missing colors have no cubes
+pub type Bag = HashMap<Color, u32>;
+
+/// How the cubes of a set are drawn from the bag
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Drawing {
+    WithReplacement,
+    WithoutReplacement,
+}
+
 // parse the line "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green" so that it returns a Game struct
 pub fn parse_game(line: &str) -> Game {
     let mut parts = line.split(": ");
@@ -41,13 +53,185 @@ fn parse_set(set: &str) -> Set {
     for item in set.split(", ") {
         let mut parts = item.split(" ");
         let number = parts.next().unwrap().parse::<u32>().unwrap();
-        let color = match parts.next().unwrap() {
-            "red" => Color::Red,
-            "blue" => Color::Blue,
-            "green" => Color::Green,
-            _ => panic!("Invalid color"),
-        };
+        let color = Color::new(parts.next().unwrap());
         draw.insert(color, number);
     }
     Set { draw }
 }
+
+// check if a game is possible given a bag
+// to be possible a game must not have sets where the draw for a particular color
+// exceeds the number of cubes of that color in the bag
+pub fn game_is_possible(game: &Game, bag: &Bag) -> bool {
+    game.sets.iter().all(|set| set.draw.iter().all(|(color, number)| bag.get(color).copied().unwrap_or(0) >= *number))
+}
+
+// Get the max value of each color seen in one of the sets of the game
+pub fn get_minimal_distribution(game: &Game) -> Bag {
+    let mut distribution = HashMap::new();
+    for set in &game.sets {
+        for (color, &number) in &set.draw {
+            let current = distribution.entry(color.clone()).or_insert(0);
+            if number > *current {
+                *current = number;
+            }
+        }
+    }
+    distribution
+}
+
+/// The numbers of the games that can't be played with the given bag,
+/// e.g. because they draw colors that are not in it
+pub fn impossible_games(games: &[Game], bag: &Bag) -> Vec<u32> {
+    games.iter().filter(|game| !game_is_possible(game, bag)).map(|game| game.number).collect()
+}
+
+/// What can be inferred about the bag from a list of games
+pub struct BagInference<'a> {
+    games: &'a [Game],
+    minimal_bag: Bag,
+}
+
+impl<'a> BagInference<'a> {
+    pub fn new(games: &'a [Game]) -> BagInference<'a> {
+        let mut minimal_bag = Bag::new();
+        for distribution in games.iter().map(get_minimal_distribution) {
+            for (color, number) in distribution {
+                let current = minimal_bag.entry(color).or_insert(0);
+                *current = number.max(*current);
+            }
+        }
+        BagInference { games, minimal_bag }
+    }
+
+    /// The smallest bag allowing every game, a bag is consistent with the games if and only if
+    /// it has at least as many cubes of each color
+    pub fn minimal_bag(&self) -> &Bag {
+        &self.minimal_bag
+    }
+
+    pub fn is_consistent(&self, bag: &Bag) -> bool {
+        self.games.iter().all(|game| game_is_possible(game, bag))
+    }
+
+    /// Every consistent bag containing exactly `total` cubes of the colors seen in the games
+    pub fn consistent_bags(&self, total: u32) -> Vec<Bag> {
+        let mut colors: Vec<&Color> = self.minimal_bag.keys().collect();
+        colors.sort();
+        let mut bags = Vec::new();
+        let minimum_total: u32 = self.minimal_bag.values().sum();
+        if minimum_total <= total {
+            self.fill_bag(&colors, total - minimum_total, &mut Bag::new(), &mut bags);
+        }
+        bags
+    }
+
+    fn fill_bag(&self, colors: &[&Color], remaining: u32, bag: &mut Bag, bags: &mut Vec<Bag>) {
+        match colors {
+            [] => {
+                if remaining == 0 {
+                    bags.push(bag.clone());
+                }
+            }
+            [color] => {
+                bag.insert((*color).clone(), self.minimal_bag[*color] + remaining);
+                self.fill_bag(&[], 0, bag, bags);
+            }
+            [color, rest @ ..] => {
+                for extra in 0..=remaining {
+                    bag.insert((*color).clone(), self.minimal_bag[*color] + extra);
+                    self.fill_bag(rest, remaining - extra, bag, bags);
+                }
+            }
+        }
+    }
+
+    /// The natural logarithm of the probability of drawing every set of every game from the bag.
+    /// The cubes of a set are drawn uniformly at random, and put back in the bag between sets.
+    pub fn log_likelihood(&self, bag: &Bag, drawing: Drawing) -> f64 {
+        let total: u32 = bag.values().sum();
+        let mut log_likelihood = 0.0;
+        for set in self.games.iter().flat_map(|game| &game.sets) {
+            let drawn: u32 = set.draw.values().sum();
+            log_likelihood += match drawing {
+                Drawing::WithReplacement => {
+                    let mut log_probability = ln_factorial(drawn);
+                    for (color, &number) in &set.draw {
+                        let cubes = bag.get(color).copied().unwrap_or(0);
+                        log_probability += number as f64 * (cubes as f64 / total as f64).ln() - ln_factorial(number);
+                    }
+                    log_probability
+                }
+                Drawing::WithoutReplacement => {
+                    let mut log_probability = -ln_binomial(total, drawn);
+                    for (color, &number) in &set.draw {
+                        log_probability += ln_binomial(bag.get(color).copied().unwrap_or(0), number);
+                    }
+                    log_probability
+                }
+            };
+        }
+        log_likelihood
+    }
+
+    /// The consistent bag of `total` cubes that makes the games the most likely, if any
+    pub fn most_likely_bag(&self, total: u32, drawing: Drawing) -> Option<Bag> {
+        self.consistent_bags(total)
+            .into_iter()
+            .map(|bag| (self.log_likelihood(&bag, drawing), bag))
+            .fold(None, |best: Option<(f64, Bag)>, (log_likelihood, bag)| match best {
+                Some((best_log_likelihood, _)) if best_log_likelihood >= log_likelihood => best,
+                _ => Some((log_likelihood, bag)),
+            })
+            .map(|(_, bag)| bag)
+    }
+}
+
+fn ln_factorial(n: u32) -> f64 {
+    (2..=n).map(|k| (k as f64).ln()).sum()
+}
+
+fn ln_binomial(n: u32, k: u32) -> f64 {
+    if k > n {
+        return f64::NEG_INFINITY;
+    }
+    ln_factorial(n) - ln_factorial(k) - ln_factorial(n - k)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bag(cubes: &[(&str, u32)]) -> Bag {
+        cubes.iter().map(|&(color, number)| (Color::new(color), number)).collect()
+    }
+
+    #[test]
+    fn test_consistent_bags() {
+        let games = vec![parse_game("Game 1: 3 red, 1 blue; 2 red")];
+        let inference = BagInference::new(&games);
+        assert_eq!(inference.minimal_bag(), &bag(&[("red", 3), ("blue", 1)]));
+        assert_eq!(inference.consistent_bags(3), Vec::<Bag>::new());
+        assert_eq!(inference.consistent_bags(4), vec![bag(&[("red", 3), ("blue", 1)])]);
+        assert_eq!(inference.consistent_bags(6).len(), 3);
+        assert!(inference.consistent_bags(6).iter().all(|bag| inference.is_consistent(bag)));
+    }
+
+    #[test]
+    fn test_most_likely_bag() {
+        let games = vec![parse_game("Game 1: 3 red, 1 blue")];
+        let inference = BagInference::new(&games);
+        assert_eq!(inference.most_likely_bag(8, Drawing::WithReplacement), Some(bag(&[("red", 6), ("blue", 2)])));
+        assert_eq!(inference.most_likely_bag(8, Drawing::WithoutReplacement), Some(bag(&[("red", 6), ("blue", 2)])));
+        assert_eq!(inference.most_likely_bag(3, Drawing::WithReplacement), None);
+        let probability = inference.log_likelihood(&bag(&[("red", 3), ("blue", 1)]), Drawing::WithoutReplacement).exp();
+        assert!((probability - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_impossible_games_with_new_colors() {
+        let games = vec![parse_game("Game 1: 3 blue, 4 red"), parse_game("Game 2: 1 yellow, 2 red"), parse_game("Game 3: 13 green")];
+        let bag = bag(&[("red", 12), ("green", 13), ("blue", 14)]);
+        assert_eq!(impossible_games(&games, &bag), vec![2]);
+    }
+}
@@ -5,26 +5,12 @@ use crate::common::{Color, Game};
 
 
 pub fn run(input: &str) -> String {
-    let target_distribution = HashMap::from([(Color::Red, 12), (Color::Blue, 14), (Color::Green, 13)]);
-    let games: Vec<_> = input.lines().map(|line| common::parse_game(line)).collect();
-    let result = games.into_iter().filter(|game| game_is_possible(game, &target_distribution)).map(|game| game.number).sum::<u32>();
+    let target_distribution = HashMap::from([(Color::new("red"), 12), (Color::new("blue"), 14), (Color::new("green"), 13)]);
+    let games: Vec<Game> = input.lines().map(common::parse_game).collect();
+    let result = games.into_iter().filter(|game| common::game_is_possible(game, &target_distribution)).map(|game| game.number).sum::<u32>();
     return result.to_string();
 }
 
-// check if a game is possible given a target distribution
-// to be possible a game must not have sets where the draw for a particular color
-// exceeds the target distribution
-fn game_is_possible(game: &Game, target_distribution: &HashMap<Color, u32>) -> bool {
-    for set in &game.sets {
-        for (color, number) in &set.draw {
-            if target_distribution[color] < *number {
-                return false;
-            }
-        }
-    }
-    true
-}
-
 
 
 #[cfg(test)]
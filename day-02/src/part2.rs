@@ -1,31 +1,15 @@
-use std::collections::HashMap;
-
-use crate::common::{self, Color, Game};
+use crate::common;
 
 pub fn run(input: &str) -> String {
-    let games: Vec<_> = input.lines().map(|line| common::parse_game(line)).collect();
+    let games: Vec<_> = input.lines().map(common::parse_game).collect();
     let result: u32 = games
-    .into_iter()
-    .map(|game| get_minimal_distribution(game))
+    .iter()
+    .map(common::get_minimal_distribution)
     .map(|distribution| distribution.into_values().fold(1, |acc, x| acc * x))
     .sum();
     return result.to_string();
 }
 
-// Get the max value of each color seen in one of the sets of the game
-fn get_minimal_distribution(game: Game) -> HashMap<Color, u32> {
-    let mut distribution = HashMap::new();
-    for set in game.sets {
-        for (color, number) in set.draw {
-            let current = distribution.entry(color).or_insert(0);
-            if number > *current {
-                *current = number;
-            }
-        }
-    }
-    distribution
-}
-
 #[cfg(test)]
 mod tests {
 
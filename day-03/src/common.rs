@@ -1,84 +1,186 @@
+use std::{collections::HashMap, ops::Range};
+
 use regex::Regex;
 
-pub fn parse_and_analyze_engine<'a>(input: &'a str) -> (Vec<Number>, Vec<Symbol<'a>>) {
-    let mut part_numbers = Vec::new();
-    let mut gears = Vec::new();
-    let mut previous_row_numbers = Vec::new();
-    let mut previous_row_symbols = Vec::new();
-    for line in input.lines() {
-        let (mut numbers, mut symbols) = parse_row(line);
-        for symbol in &mut symbols {
-            for number in &mut previous_row_numbers {
-                if are_adjacent(number, symbol) {
-                    number.is_part_number = true;
-                    symbol.neighbors.push(number.value);
-                }
-            }
-            for number in &mut numbers {
-                if are_adjacent(number, symbol) {
-                    number.is_part_number = true;
-                    symbol.neighbors.push(number.value);
-                }
+/// A number of the schematic, spanning `columns` on its row
+#[derive(Debug, PartialEq, Eq)]
+pub struct Number {
+    pub value: u32,
+    pub row: usize,
+    pub columns: Range<usize>,
+}
+
+/// A symbol of the schematic, with the indexes of its adjacent numbers
+#[derive(Debug, PartialEq, Eq)]
+pub struct Symbol {
+    pub character: char,
+    pub row: usize,
+    pub column: usize,
+    pub neighbors: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NeighborCount {
+    Exactly(usize),
+    AtLeast(usize),
+}
+
+/// The engine schematic, with every number and symbol indexed by position
+pub struct Schematic {
+    pub numbers: Vec<Number>,
+    pub symbols: Vec<Symbol>,
+    /// For each number, the indexes of its adjacent symbols
+    number_neighbors: Vec<Vec<usize>>,
+}
+
+impl NeighborCount {
+    fn matches(&self, count: usize) -> bool {
+        match *self {
+            NeighborCount::Exactly(expected) => count == expected,
+            NeighborCount::AtLeast(minimum) => count >= minimum,
+        }
+    }
+}
+
+impl Schematic {
+    pub fn parse(input: &str) -> Schematic {
+        let number_re = Regex::new(r"\d+").unwrap();
+        let symbol_re = Regex::new(r"[^.\d]").unwrap();
+        let mut numbers = Vec::new();
+        let mut symbols = Vec::new();
+        for (row, line) in input.lines().enumerate() {
+            numbers.extend(number_re.find_iter(line).map(|m| Number {
+                value: m.as_str().parse().unwrap(),
+                row,
+                columns: m.start()..m.end(),
+            }));
+            symbols.extend(symbol_re.find_iter(line).map(|m| Symbol {
+                character: m.as_str().chars().next().unwrap(),
+                row,
+                column: m.start(),
+                neighbors: Vec::new(),
+            }));
+        }
+        let mut number_at_position = HashMap::new();
+        for (number_index, number) in numbers.iter().enumerate() {
+            for column in number.columns.clone() {
+                number_at_position.insert((number.row, column), number_index);
             }
         }
-        for symbol in &mut previous_row_symbols {
-            for number in &mut numbers {
-                if are_adjacent(number, symbol) {
-                    number.is_part_number = true;
-                    symbol.neighbors.push(number.value);
+        let mut number_neighbors = vec![Vec::new(); numbers.len()];
+        for (symbol_index, symbol) in symbols.iter_mut().enumerate() {
+            for row in symbol.row.saturating_sub(1)..=symbol.row + 1 {
+                for column in symbol.column.saturating_sub(1)..=symbol.column + 1 {
+                    if let Some(&number_index) = number_at_position.get(&(row, column)) {
+                        if !symbol.neighbors.contains(&number_index) {
+                            symbol.neighbors.push(number_index);
+                            number_neighbors[number_index].push(symbol_index);
+                        }
+                    }
                 }
             }
         }
-        let mut new_part_numbers: Vec<Number> = previous_row_numbers.into_iter().filter(|n| n.is_part_number).collect();
-        part_numbers.append(&mut new_part_numbers);
-        let mut new_gears = previous_row_symbols.into_iter().filter(|s| s.character == "*" && s.neighbors.len() == 2).collect();
-        gears.append(&mut new_gears);
-        previous_row_numbers = numbers;
-        previous_row_symbols = symbols;
+        Schematic { numbers, symbols, number_neighbors }
     }
-    let mut new_part_numbers: Vec<Number> = previous_row_numbers.into_iter().filter(|n| n.is_part_number).collect();
-    part_numbers.append(&mut new_part_numbers);
-    let mut new_gears = previous_row_symbols.into_iter().filter(|s| s.character == "*" && s.neighbors.len() == 2).collect();
-    gears.append(&mut new_gears);
-    (part_numbers, gears)
-}
 
-fn are_adjacent(number: &Number, symbol: &Symbol) -> bool {
-    let (number_start, number_end) = number.coordinates;
-    let number_start = number_start as i32;
-    let number_end = number_end as i32;
-    let symbol_index = symbol.index as i32;
-    symbol_index >= number_start - 1 && symbol_index <= number_end
-}
+    /// The numbers adjacent to a symbol
+    pub fn neighbors<'a>(&'a self, symbol: &'a Symbol) -> impl Iterator<Item = &'a Number> {
+        symbol.neighbors.iter().map(|&number_index| &self.numbers[number_index])
+    }
 
-pub struct Number {
-    pub value: u32,
-    pub is_part_number: bool,
-    pub coordinates: (usize, usize),
-}
+    /// The numbers adjacent to at least one symbol
+    pub fn part_numbers(&self) -> impl Iterator<Item = &Number> {
+        self.numbers_where(|symbols| !symbols.is_empty())
+    }
 
-pub struct Symbol<'a> {
-    pub index: usize,
-    pub character: &'a str,
-    pub neighbors: Vec<u32>,
-}
+    /// The numbers adjacent to no symbol at all
+    pub fn isolated_numbers(&self) -> impl Iterator<Item = &Number> {
+        self.numbers_where(|symbols| symbols.is_empty())
+    }
+
+    /// The numbers adjacent to at least one symbol with the given character
+    pub fn numbers_adjacent_to(&self, character: char) -> impl Iterator<Item = &Number> {
+        self.numbers_where(move |symbols| symbols.iter().any(|&symbol_index| self.symbols[symbol_index].character == character))
+    }
+
+    /// The symbols whose count of adjacent numbers matches `count`
+    pub fn symbols_with(&self, count: NeighborCount) -> impl Iterator<Item = &Symbol> {
+        self.symbols.iter().filter(move |symbol| count.matches(symbol.neighbors.len()))
+    }
 
-fn parse_row<'a>(line: &'a str) -> (Vec<Number>, Vec<Symbol<'a>>) {
-    let re = Regex::new(r"\d+").unwrap();
-    let numbers = re.find_iter(line).map(|m| {
-        Number {
-            value: m.as_str().parse().unwrap(),
-            is_part_number: false,
-            coordinates: (m.start(), m.end()),
+    /// For each symbol character, the sum of the numbers adjacent to symbols with that character
+    pub fn sum_by_symbol(&self) -> HashMap<char, u64> {
+        let mut sums = HashMap::new();
+        for symbol in &self.symbols {
+            *sums.entry(symbol.character).or_insert(0) += self.neighbors(symbol).map(|number| number.value as u64).sum::<u64>();
         }
-    }).collect();
-    let re = Regex::new(r"[^.\d]").unwrap();
-    let symbols = re.find_iter(line).map(|m| {
-        Symbol {
-            index: m.start(),
-            character: m.as_str(),
-            neighbors: Vec::new(),
+        sums
+    }
+
+    /// For each symbol character, the sum over symbols matching `count`
+    /// of the product of their adjacent numbers, e.g. the gear ratios for `*` with exactly two neighbors
+    pub fn product_sum_by_symbol(&self, count: NeighborCount) -> HashMap<char, u64> {
+        let mut sums = HashMap::new();
+        for symbol in self.symbols_with(count) {
+            *sums.entry(symbol.character).or_insert(0) += self.neighbors(symbol).map(|number| number.value as u64).product::<u64>();
         }
-    }).collect();
-    (numbers, symbols)
-} 
\ No newline at end of file
+        sums
+    }
+
+    fn numbers_where<F: Fn(&[usize]) -> bool>(&self, predicate: F) -> impl Iterator<Item = &Number> {
+        self.numbers.iter().zip(&self.number_neighbors).filter(move |(_, symbols)| predicate(symbols)).map(|(number, _)| number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = "467..114..
+...*......
+..35..633.
+......#...
+617*......
+.....+.58.
+..592.....
+......755.
+...$.*....
+.664.598..";
+
+    #[test]
+    fn test_isolated_numbers() {
+        let schematic = Schematic::parse(INPUT);
+        let isolated: Vec<u32> = schematic.isolated_numbers().map(|number| number.value).collect();
+        assert_eq!(isolated, vec![114, 58]);
+    }
+
+    #[test]
+    fn test_numbers_adjacent_to() {
+        let schematic = Schematic::parse(INPUT);
+        let mut values: Vec<u32> = schematic.numbers_adjacent_to('*').map(|number| number.value).collect();
+        values.sort();
+        assert_eq!(values, vec![35, 467, 598, 617, 755]);
+        let values: Vec<u32> = schematic.numbers_adjacent_to('#').map(|number| number.value).collect();
+        assert_eq!(values, vec![633]);
+    }
+
+    #[test]
+    fn test_symbols_with() {
+        let schematic = Schematic::parse(INPUT);
+        let single: Vec<(usize, usize)> = schematic.symbols_with(NeighborCount::Exactly(1)).map(|symbol| (symbol.row, symbol.column)).collect();
+        assert_eq!(single, vec![(3, 6), (4, 3), (5, 5), (8, 3)]);
+        assert_eq!(schematic.symbols_with(NeighborCount::AtLeast(2)).count(), 2);
+        assert_eq!(schematic.symbols_with(NeighborCount::AtLeast(0)).count(), 6);
+    }
+
+    #[test]
+    fn test_sums_and_products() {
+        let schematic = Schematic::parse(INPUT);
+        let sums = schematic.sum_by_symbol();
+        assert_eq!(sums[&'#'], 633);
+        assert_eq!(sums[&'*'], 467 + 35 + 617 + 755 + 598);
+        let products = schematic.product_sum_by_symbol(NeighborCount::Exactly(1));
+        assert_eq!(products[&'*'], 617);
+        assert_eq!(products[&'$'], 664);
+    }
+}
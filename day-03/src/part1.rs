@@ -1,8 +1,8 @@
-use crate::common;
+use crate::common::Schematic;
 
 pub fn run(input: &str) -> String {
-    let (part_numbers, _) = common::parse_and_analyze_engine(input);
-    let result = part_numbers.iter().map(|n| n.value).sum::<u32>();
+    let schematic = Schematic::parse(input);
+    let result = schematic.part_numbers().map(|n| n.value).sum::<u32>();
     result.to_string()
 }
 
@@ -1,8 +1,9 @@
-use crate::common;
+use crate::common::{NeighborCount, Schematic};
 
 pub fn run(input: &str) -> String {
-    let (_, gears) = common::parse_and_analyze_engine(input);
-    let total_ratio = gears.iter().map(|gear| gear.neighbors[0] * gear.neighbors[1]).sum::<u32>();
+    let schematic = Schematic::parse(input);
+    let gear_ratios = schematic.product_sum_by_symbol(NeighborCount::Exactly(2));
+    let total_ratio = gear_ratios.get(&'*').copied().unwrap_or(0);
     total_ratio.to_string()
 }
 
@@ -20,4 +20,109 @@ impl Card {
     pub fn count_matching_numbers(&self) -> u32 {
         self.numbers.iter().filter(|&n| self.winning_numbers.contains(n)).count() as u32
     }
-}
\ No newline at end of file
+}
+
+/// The result of the scratchcard cascade, where each card wins copies of the cards following it
+pub struct Cascade {
+    /// The card numbers, in the order of the input
+    pub card_numbers: Vec<u32>,
+    /// The total count of each card, the original included
+    pub counts: Vec<u64>,
+    /// The index of the last card won by each card, its own index if it wins nothing
+    last_won: Vec<usize>,
+}
+
+impl Cascade {
+    /// Computes the cascade with a difference array, wins running past the last card are ignored
+    pub fn compute(cards: &[Card]) -> Cascade {
+        let card_count = cards.len();
+        let mut differences = vec![0i64; card_count + 1];
+        let mut counts = Vec::with_capacity(card_count);
+        let mut last_won_cards = Vec::with_capacity(card_count);
+        let mut copies = 0i64;
+        for (card_index, card) in cards.iter().enumerate() {
+            copies += differences[card_index];
+            let count = 1 + copies as u64;
+            counts.push(count);
+            let last_won = (card_index + card.count_matching_numbers() as usize).min(card_count - 1);
+            if last_won > card_index {
+                differences[card_index + 1] += count as i64;
+                differences[last_won + 1] -= count as i64;
+            }
+            last_won_cards.push(last_won.max(card_index));
+        }
+        let card_numbers = cards.iter().map(|card| card.card_number).collect();
+        Cascade { card_numbers, counts, last_won: last_won_cards }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// The copies received by a card, as (originating card number, copies).
+    /// It is computed on demand from the cards won by the earlier cards, each of their copies winning one copy.
+    pub fn provenance(&self, card_index: usize) -> impl Iterator<Item = (u32, u64)> + '_ {
+        (0..card_index)
+            .filter(move |&origin| self.last_won[origin] >= card_index)
+            .map(|origin| (self.card_numbers[origin], self.counts[origin]))
+    }
+
+    /// Renders the cascade as a DOT graph, with an edge for each card that won copies of another
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph cascade {\n");
+        for (card_index, card_number) in self.card_numbers.iter().enumerate() {
+            dot.push_str(&format!("    {} [label=\"Card {} x{}\"];\n", card_number, card_number, self.counts[card_index]));
+        }
+        for (card_index, card_number) in self.card_numbers.iter().enumerate() {
+            for (origin, copies) in self.provenance(card_index) {
+                dot.push_str(&format!("    {} -> {} [label=\"{}\"];\n", origin, card_number, copies));
+            }
+        }
+        dot.push('}');
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
+Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
+Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
+Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
+
+    fn parse_cards(input: &str) -> Vec<Card> {
+        input.lines().map(|line| Card::parse(line).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_counts_and_provenance() {
+        let cascade = Cascade::compute(&parse_cards(INPUT));
+        assert_eq!(cascade.counts, vec![1, 2, 4, 8, 14, 1]);
+        assert_eq!(cascade.provenance(3).collect::<Vec<_>>(), vec![(1, 1), (2, 2), (3, 4)]);
+        assert_eq!(cascade.provenance(0).count(), 0);
+    }
+
+    #[test]
+    fn test_wins_past_the_last_card() {
+        let cards = parse_cards("Card 1: 1 2 3 | 1 2 3
+Card 2: 4 5 | 4 5");
+        let cascade = Cascade::compute(&cards);
+        assert_eq!(cascade.counts, vec![1, 2]);
+        assert_eq!(cascade.total(), 3);
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let cascade = Cascade::compute(&parse_cards("Card 1: 1 | 1
+Card 2: 2 | 3"));
+        assert_eq!(cascade.to_dot(), "digraph cascade {
+    1 [label=\"Card 1 x1\"];
+    2 [label=\"Card 2 x2\"];
+    1 -> 2 [label=\"1\"];
+}");
+    }
+}
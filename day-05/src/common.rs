@@ -1,16 +1,9 @@
-use std::collections::HashMap;
-
-#[derive(Eq, PartialEq, Hash, Clone, Debug)]
-pub enum Category {
-    Seed,
-    Soil,
-    Fertilizer,
-    Water,
-    Light,
-    Temperature,
-    Humidity,
-    Location,
-}
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::ops::Range;
+
+#[derive(Eq, PartialEq, Hash, Clone, Debug, PartialOrd, Ord)]
+pub struct Category(String);
 
 pub struct Mapping {
     pub source: Category,
@@ -18,74 +11,227 @@ pub struct Mapping {
     pub ranges: Vec<MappingRange>,
 }
 
-
-
 pub struct MappingRange {
     pub destination_start: u64,
     pub source_start: u64,
     pub range_length: u64,
 }
 
+/// The mappings of an almanac, seen as a graph whose nodes are categories.
+/// The source of the first mapping is the root of the graph.
+pub struct MappingGraph {
+    mappings: Vec<Mapping>,
+    outgoing: HashMap<Category, Vec<usize>>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum AlmanacError {
+    InvalidHeader(String),
+    InvalidRange(String),
+    DuplicateMapping { source: Category, destination: Category },
+    /// The category is the source of mappings to several destinations
+    DuplicateSource { source: Category, destinations: (Category, Category) },
+    OverlappingRanges { source: Category, destination: Category, first: Range<u64>, second: Range<u64> },
+    MissingLink { root: Category, unreachable: Category },
+    NoPath { source: Category, destination: Category },
+}
+
+impl Category {
+    pub fn new(name: &str) -> Category {
+        Category(name.to_string())
+    }
+
+    pub fn seed() -> Category {
+        Category::new("seed")
+    }
+
+    pub fn location() -> Category {
+        Category::new("location")
+    }
+
+    pub fn name(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 impl Mapping {
     /// Parses the input and return a Mapping
-    pub fn parse(mapping_input: &str) -> Mapping {
+    pub fn parse(mapping_input: &str) -> Result<Mapping, AlmanacError> {
         let mut lines = mapping_input.lines();
-        let (source, destination) = parse_mapping_header(lines.next().unwrap());
-        let ranges = lines.map(|line| MappingRange::parse(line)).collect();
-        Mapping { source, destination, ranges }
+        let header = lines.next().unwrap_or_default();
+        let (source, destination) = parse_mapping_header(header).ok_or_else(|| AlmanacError::InvalidHeader(header.to_string()))?;
+        let ranges = lines.map(MappingRange::parse).collect::<Result<_, _>>()?;
+        Ok(Mapping { source, destination, ranges })
     }
-}
 
-impl Category {
-    fn parse(category: &str) -> Category {
-        match category {
-            "seed" => Category::Seed,
-            "soil" => Category::Soil,
-            "fertilizer" => Category::Fertilizer,
-            "water" => Category::Water,
-            "light" => Category::Light,
-            "temperature" => Category::Temperature,
-            "humidity" => Category::Humidity,
-            "location" => Category::Location,
-            _ => panic!("Unknown category: {}", category),
+    /// Checks that no two ranges of the mapping share a source value
+    fn check_overlaps(&self) -> Result<(), AlmanacError> {
+        let mut source_ranges: Vec<Range<u64>> = self.ranges.iter().map(|range| range.source_range()).collect();
+        source_ranges.sort_by_key(|range| range.start);
+        match source_ranges.windows(2).find(|pair| pair[0].end > pair[1].start) {
+            Some(pair) => Err(AlmanacError::OverlappingRanges {
+                source: self.source.clone(),
+                destination: self.destination.clone(),
+                first: pair[0].clone(),
+                second: pair[1].clone(),
+            }),
+            None => Ok(()),
         }
     }
 }
 
-fn parse_mapping_header(line: &str) -> (Category, Category) {
-    let categories_part = line.split_whitespace().nth(0).unwrap();
-    let mut categories = categories_part.split("-to-");
-    let source = categories.next().unwrap();
-    let destination = categories.next().unwrap();
-    (Category::parse(source), Category::parse(destination))
+fn parse_mapping_header(line: &str) -> Option<(Category, Category)> {
+    let (categories_part, suffix) = line.split_once(' ')?;
+    if suffix.trim() != "map:" {
+        return None;
+    }
+    let (source, destination) = categories_part.split_once("-to-")?;
+    if source.is_empty() || destination.is_empty() {
+        return None;
+    }
+    Some((Category::new(source), Category::new(destination)))
 }
 
 impl MappingRange {
-    fn parse(line: &str) -> MappingRange {
-        let mut numbers = line.split_whitespace().map(|x| x.parse().unwrap());
-        let destination_start = numbers.next().unwrap();
-        let source_start = numbers.next().unwrap();
-        let range_length = numbers.next().unwrap();
-        MappingRange { destination_start, source_start, range_length }
+    fn parse(line: &str) -> Result<MappingRange, AlmanacError> {
+        let invalid = || AlmanacError::InvalidRange(line.to_string());
+        let numbers = line.split_whitespace().map(|x| x.parse().map_err(|_| invalid())).collect::<Result<Vec<u64>, _>>()?;
+        match numbers[..] {
+            [destination_start, source_start, range_length] => Ok(MappingRange { destination_start, source_start, range_length }),
+            _ => Err(invalid()),
+        }
     }
 }
 
-pub trait MappingCollection {
-    fn get_mappings(&self)-> &HashMap<Category, Mapping>;
+impl MappingGraph {
+    /// Parses the mapping blocks of an almanac and validates the resulting graph
+    pub fn parse(input: &str) -> Result<MappingGraph, AlmanacError> {
+        let mappings = input.split("\n\n").filter(|block| !block.trim().is_empty()).map(Mapping::parse).collect::<Result<_, _>>()?;
+        MappingGraph::new(mappings)
+    }
 
-    fn map_seed_to_location(&self, seed: u64) -> u64 {
-        let mut mapped_value = seed;
-        let mut category = &Category::Seed;
-        let mappings = self.get_mappings();
-        loop {
-            let mapping = mappings.get(&category).unwrap();
-            category = &mapping.destination;
-            mapped_value = mapping.map_value(mapped_value);
-            if category == &Category::Location {
-                break;
+    /// Builds the graph, rejecting duplicate mappings, categories mapped to several destinations,
+    /// overlapping ranges, and categories that can't be reached from the root
+    pub fn new(mappings: Vec<Mapping>) -> Result<MappingGraph, AlmanacError> {
+        let mut outgoing: HashMap<Category, Vec<usize>> = HashMap::new();
+        let mut links = HashSet::new();
+        for (index, mapping) in mappings.iter().enumerate() {
+            if !links.insert((&mapping.source, &mapping.destination)) {
+                return Err(AlmanacError::DuplicateMapping { source: mapping.source.clone(), destination: mapping.destination.clone() });
+            }
+            if let Some(&other) = outgoing.get(&mapping.source).and_then(|indexes| indexes.first()) {
+                let destinations = (mappings[other].destination.clone(), mapping.destination.clone());
+                return Err(AlmanacError::DuplicateSource { source: mapping.source.clone(), destinations });
             }
+            mapping.check_overlaps()?;
+            outgoing.entry(mapping.source.clone()).or_default().push(index);
         }
-        mapped_value
+        let graph = MappingGraph { mappings, outgoing };
+        if let Some(root) = graph.root() {
+            let reachable = graph.reachable_from(root);
+            let unreachable = graph.mappings.iter().flat_map(|m| [&m.source, &m.destination]).find(|c| !reachable.contains(c));
+            if let Some(unreachable) = unreachable {
+                return Err(AlmanacError::MissingLink { root: root.clone(), unreachable: unreachable.clone() });
+            }
+        }
+        Ok(graph)
+    }
+
+    pub fn root(&self) -> Option<&Category> {
+        self.mappings.first().map(|mapping| &mapping.source)
+    }
+
+    pub fn mappings(&self) -> &[Mapping] {
+        &self.mappings
+    }
+
+    /// Returns the mappings going out of a category
+    pub fn mappings_from<'a>(&'a self, category: &Category) -> impl Iterator<Item = &'a Mapping> {
+        self.outgoing.get(category).into_iter().flatten().map(|&index| &self.mappings[index])
+    }
+
+    /// Finds the shortest chain of mappings going from `source` to `destination`,
+    /// failing if `destination` can't be reached from `source`
+    pub fn path(&self, source: &Category, destination: &Category) -> Result<Vec<&Mapping>, AlmanacError> {
+        let mut previous: HashMap<&Category, &Mapping> = HashMap::new();
+        let mut visited = HashSet::from([source]);
+        let mut queue = VecDeque::from([source]);
+        while let Some(category) = queue.pop_front() {
+            if category == destination {
+                let mut path = Vec::new();
+                let mut current = category;
+                while let Some(mapping) = previous.get(current) {
+                    path.push(*mapping);
+                    current = &mapping.source;
+                }
+                path.reverse();
+                return Ok(path);
+            }
+            for mapping in self.mappings_from(category) {
+                if visited.insert(&mapping.destination) {
+                    previous.insert(&mapping.destination, mapping);
+                    queue.push_back(&mapping.destination);
+                }
+            }
+        }
+        Err(AlmanacError::NoPath { source: source.clone(), destination: destination.clone() })
+    }
+
+    fn reachable_from<'a>(&'a self, root: &'a Category) -> HashSet<&'a Category> {
+        let mut reachable = HashSet::from([root]);
+        let mut stack = vec![root];
+        while let Some(category) = stack.pop() {
+            for mapping in self.mappings_from(category) {
+                if reachable.insert(&mapping.destination) {
+                    stack.push(&mapping.destination);
+                }
+            }
+        }
+        reachable
+    }
+}
+
+impl fmt::Display for AlmanacError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlmanacError::InvalidHeader(line) => write!(f, "Invalid mapping header: {}", line),
+            AlmanacError::InvalidRange(line) => write!(f, "Invalid mapping range: {}", line),
+            AlmanacError::DuplicateMapping { source, destination } => write!(f, "Duplicate mapping from {} to {}", source, destination),
+            AlmanacError::OverlappingRanges { source, destination, first, second } => {
+                write!(f, "Overlapping ranges {:?} and {:?} in mapping from {} to {}", first, second, source, destination)
+            }
+            AlmanacError::DuplicateSource { source, destinations: (first, second) } => {
+                write!(f, "Category {} is mapped to both {} and {}", source, first, second)
+            }
+            AlmanacError::MissingLink { root, unreachable } => write!(f, "Category {} can't be reached from {}", unreachable, root),
+            AlmanacError::NoPath { source, destination } => write!(f, "Category {} can't be reached from {}", destination, source),
+        }
+    }
+}
+
+pub trait MappingCollection {
+    fn get_mappings(&self) -> &MappingGraph;
+
+    /// Maps a value through the chain of mappings going from `source` to `destination`
+    fn map_value(&self, source: &Category, destination: &Category, value: u64) -> Option<u64> {
+        let path = self.get_mappings().path(source, destination).ok()?;
+        Some(path.into_iter().fold(value, |mapped_value, mapping| mapping.map_value(mapped_value)))
+    }
+
+    fn map_seed_to_location(&self, seed: u64) -> u64 {
+        self.map_value(&Category::seed(), &Category::location(), seed).expect("No path from seed to location")
+    }
+}
+
+impl MappingCollection for MappingGraph {
+    fn get_mappings(&self) -> &MappingGraph {
+        self
     }
 }
 
@@ -100,4 +246,54 @@ impl Mapping {
         }
         mapped_value
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chain_graph() {
+        let input = "a-to-b map:
+10 0 5
+
+b-to-c map:
+0 10 5
+
+c-to-d map:
+100 0 50";
+        let graph = MappingGraph::parse(input).unwrap();
+        assert_eq!(graph.root(), Some(&Category::new("a")));
+        let path: Vec<_> = graph.path(&Category::new("a"), &Category::new("c")).unwrap().iter().map(|m| m.destination.name()).collect();
+        assert_eq!(path, vec!["b", "c"]);
+        assert_eq!(graph.map_value(&Category::new("a"), &Category::new("c"), 3), Some(3));
+        assert_eq!(graph.map_value(&Category::new("a"), &Category::new("d"), 3), Some(103));
+        assert_eq!(graph.map_value(&Category::new("c"), &Category::new("a"), 3), None);
+        assert_eq!(
+            graph.path(&Category::new("d"), &Category::new("b")).err(),
+            Some(AlmanacError::NoPath { source: Category::new("d"), destination: Category::new("b") })
+        );
+    }
+
+    #[test]
+    fn test_validation_errors() {
+        assert_eq!(
+            MappingGraph::parse("a-to-b map:\n1 2 3\n\na-to-b map:\n4 5 6").err(),
+            Some(AlmanacError::DuplicateMapping { source: Category::new("a"), destination: Category::new("b") })
+        );
+        assert_eq!(
+            MappingGraph::parse("a-to-b map:\n0 0 10\n50 5 10").err(),
+            Some(AlmanacError::OverlappingRanges { source: Category::new("a"), destination: Category::new("b"), first: 0..10, second: 5..15 })
+        );
+        assert_eq!(
+            MappingGraph::parse("a-to-b map:\n1 2 3\n\nc-to-d map:\n4 5 6").err(),
+            Some(AlmanacError::MissingLink { root: Category::new("a"), unreachable: Category::new("c") })
+        );
+        assert_eq!(
+            MappingGraph::parse("a-to-b map:\n1 2 3\n\na-to-c map:\n4 5 6").err(),
+            Some(AlmanacError::DuplicateSource { source: Category::new("a"), destinations: (Category::new("b"), Category::new("c")) })
+        );
+        assert_eq!(MappingGraph::parse("a-b map:\n1 2 3").err(), Some(AlmanacError::InvalidHeader("a-b map:".to_string())));
+        assert_eq!(MappingGraph::parse("a-to-b map:\n1 2").err(), Some(AlmanacError::InvalidRange("1 2".to_string())));
+    }
+}
@@ -0,0 +1,276 @@
+use std::ops::Range;
+
+use crate::common::{Category, Mapping, MappingCollection};
+
+/// A contiguous range of source values that are all shifted by the same offset
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Segment {
+    pub source: Range<u64>,
+    pub offset: i64,
+}
+
+/// A piecewise-linear function over `u64` made of sorted, non-overlapping segments.
+/// The segments cover the whole domain, values that are not mapped use an offset of 0.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PiecewiseLinear {
+    segments: Vec<Segment>,
+}
+
+impl Segment {
+    fn destination(&self) -> Range<u64> {
+        shift(self.source.start, self.offset)..shift(self.source.end, self.offset)
+    }
+}
+
+impl PiecewiseLinear {
+    /// The function mapping every value to itself
+    pub fn identity() -> PiecewiseLinear {
+        PiecewiseLinear { segments: vec![Segment { source: 0..u64::MAX, offset: 0 }] }
+    }
+
+    /// Builds the function equivalent to a single mapping stage.
+    /// When ranges of the mapping overlap, the first one listed wins, like in `Mapping::map_value`.
+    pub fn from_mapping(mapping: &Mapping) -> PiecewiseLinear {
+        let mut mapped: Vec<Segment> = Vec::new();
+        for range in &mapping.ranges {
+            let offset = range.destination_start as i64 - range.source_start as i64;
+            let mut uncovered = vec![range.source_range()];
+            for segment in &mapped {
+                uncovered = uncovered.into_iter().flat_map(|part| subtract(&part, &segment.source)).collect();
+            }
+            mapped.extend(uncovered.into_iter().map(|source| Segment { source, offset }));
+        }
+        mapped.sort_by_key(|segment| segment.source.start);
+        let mut segments = Vec::new();
+        let mut cursor = 0;
+        for segment in mapped {
+            if cursor < segment.source.start {
+                segments.push(Segment { source: cursor..segment.source.start, offset: 0 });
+            }
+            cursor = segment.source.end;
+            segments.push(segment);
+        }
+        if cursor < u64::MAX {
+            segments.push(Segment { source: cursor..u64::MAX, offset: 0 });
+        }
+        PiecewiseLinear::normalized(segments)
+    }
+
+    /// The sorted segments of the function
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Returns the function applying `self` first and then `next`
+    pub fn then(&self, next: &PiecewiseLinear) -> PiecewiseLinear {
+        let mut segments = Vec::new();
+        for segment in &self.segments {
+            let image = segment.destination();
+            for next_segment in next.overlapping(&image) {
+                let start = image.start.max(next_segment.source.start);
+                let end = image.end.min(next_segment.source.end);
+                segments.push(Segment {
+                    source: shift(start, -segment.offset)..shift(end, -segment.offset),
+                    offset: segment.offset + next_segment.offset,
+                });
+            }
+        }
+        PiecewiseLinear::normalized(segments)
+    }
+
+    /// Maps a single value
+    pub fn apply(&self, value: u64) -> u64 {
+        let index = self.segments.partition_point(|segment| segment.source.end <= value);
+        match self.segments.get(index) {
+            Some(segment) if segment.source.contains(&value) => shift(value, segment.offset),
+            _ => value,
+        }
+    }
+
+    /// Maps a range of values, the resulting ranges are sorted and merged
+    pub fn map_range(&self, range: &Range<u64>) -> Vec<Range<u64>> {
+        let mut mapped: Vec<Range<u64>> = self
+            .overlapping(range)
+            .map(|segment| {
+                let start = range.start.max(segment.source.start);
+                let end = range.end.min(segment.source.end);
+                shift(start, segment.offset)..shift(end, segment.offset)
+            })
+            .collect();
+        mapped.sort_by_key(|range| range.start);
+        merge_ranges(mapped)
+    }
+
+    /// Returns the sorted ranges of values that are mapped into `range`
+    pub fn preimage(&self, range: &Range<u64>) -> Vec<Range<u64>> {
+        let preimage = self
+            .segments
+            .iter()
+            .filter_map(|segment| {
+                let image = segment.destination();
+                let start = image.start.max(range.start);
+                let end = image.end.min(range.end);
+                (start < end).then(|| shift(start, -segment.offset)..shift(end, -segment.offset))
+            })
+            .collect();
+        merge_ranges(preimage)
+    }
+
+    fn overlapping<'a>(&'a self, range: &Range<u64>) -> impl Iterator<Item = &'a Segment> {
+        let first = self.segments.partition_point(|segment| segment.source.end <= range.start);
+        let end = range.end;
+        self.segments[first..].iter().take_while(move |segment| segment.source.start < end)
+    }
+
+    fn normalized(mut segments: Vec<Segment>) -> PiecewiseLinear {
+        segments.sort_by_key(|segment| segment.source.start);
+        let mut merged: Vec<Segment> = Vec::with_capacity(segments.len());
+        for segment in segments.into_iter().filter(|segment| !segment.source.is_empty()) {
+            match merged.last_mut() {
+                Some(last) if last.offset == segment.offset && last.source.end == segment.source.start => {
+                    last.source.end = segment.source.end;
+                }
+                _ => merged.push(segment),
+            }
+        }
+        PiecewiseLinear { segments: merged }
+    }
+}
+
+pub trait ComposedMappings: MappingCollection {
+    /// Composes the chain of mappings going from `source` to `destination`.
+    /// Returns None if `destination` can't be reached from `source`.
+    fn compose(&self, source: &Category, destination: &Category) -> Option<PiecewiseLinear> {
+        let path = self.get_mappings().path(source, destination).ok()?;
+        Some(path.into_iter().fold(PiecewiseLinear::identity(), |composed, mapping| composed.then(&PiecewiseLinear::from_mapping(mapping))))
+    }
+
+    /// Returns the seed ranges that end up in the given location range
+    fn seeds_for_locations(&self, locations: &Range<u64>) -> Vec<Range<u64>> {
+        self.compose(&Category::seed(), &Category::location())
+            .map(|composed| composed.preimage(locations))
+            .unwrap_or_default()
+    }
+}
+
+impl<T: MappingCollection> ComposedMappings for T {}
+
+fn shift(value: u64, offset: i64) -> u64 {
+    value.checked_add_signed(offset).expect("Mapped value out of bounds")
+}
+
+fn subtract(range: &Range<u64>, removed: &Range<u64>) -> Vec<Range<u64>> {
+    if removed.end <= range.start || range.end <= removed.start {
+        return vec![range.clone()];
+    }
+    let mut remaining = Vec::new();
+    if range.start < removed.start {
+        remaining.push(range.start..removed.start);
+    }
+    if removed.end < range.end {
+        remaining.push(removed.end..range.end);
+    }
+    remaining
+}
+
+fn merge_ranges(ranges: Vec<Range<u64>>) -> Vec<Range<u64>> {
+    let mut merged: Vec<Range<u64>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if last.end >= range.start => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::MappingGraph;
+
+    use super::*;
+
+    const INPUT: &str = "seed-to-soil map:
+50 98 2
+52 50 48
+
+soil-to-fertilizer map:
+0 15 37
+37 52 2
+39 0 15
+
+fertilizer-to-water map:
+49 53 8
+0 11 42
+42 0 7
+57 7 4
+
+water-to-light map:
+88 18 7
+18 25 70
+
+light-to-temperature map:
+45 77 23
+81 45 19
+68 64 13
+
+temperature-to-humidity map:
+0 69 1
+1 0 69
+
+humidity-to-location map:
+60 56 37
+56 93 4";
+
+    fn parse_mappings(input: &str) -> MappingGraph {
+        MappingGraph::parse(input).unwrap()
+    }
+
+    #[test]
+    fn test_compose_seed_to_location() {
+        let mappings = parse_mappings(INPUT);
+        let composed = mappings.compose(&Category::seed(), &Category::location()).unwrap();
+        for seed in 0..200 {
+            assert_eq!(composed.apply(seed), mappings.map_seed_to_location(seed));
+        }
+        let segments = composed.segments();
+        assert!(segments.windows(2).all(|pair| pair[0].source.end == pair[1].source.start));
+    }
+
+    #[test]
+    fn test_compose_intermediate_categories() {
+        let mappings = parse_mappings(INPUT);
+        let composed = mappings.compose(&Category::new("soil"), &Category::new("humidity")).unwrap();
+        let stages = ["soil", "fertilizer", "water", "light", "temperature"];
+        let stepwise: Vec<PiecewiseLinear> = stages
+            .iter()
+            .map(|stage| PiecewiseLinear::from_mapping(mappings.mappings_from(&Category::new(stage)).next().unwrap()))
+            .collect();
+        for soil in 0..200 {
+            let expected = stepwise.iter().fold(soil, |value, stage| stage.apply(value));
+            assert_eq!(composed.apply(soil), expected);
+        }
+        assert_eq!(mappings.compose(&Category::location(), &Category::seed()), None);
+        assert_eq!(mappings.compose(&Category::new("water"), &Category::new("water")), Some(PiecewiseLinear::identity()));
+    }
+
+    #[test]
+    fn test_map_range_minimum() {
+        let mappings = parse_mappings(INPUT);
+        let composed = mappings.compose(&Category::seed(), &Category::location()).unwrap();
+        let minimum = [79..93, 55..68].iter().flat_map(|range| composed.map_range(range)).map(|range| range.start).min();
+        assert_eq!(minimum, Some(46));
+    }
+
+    #[test]
+    fn test_seeds_for_locations() {
+        let mappings = parse_mappings(INPUT);
+        let seeds = mappings.seeds_for_locations(&(46..47));
+        assert_eq!(seeds, vec![82..83]);
+        let seeds = mappings.seeds_for_locations(&(0..100));
+        for seed in 0..200 {
+            let location = mappings.map_seed_to_location(seed);
+            assert_eq!(seeds.iter().any(|range| range.contains(&seed)), location < 100);
+        }
+    }
+}
@@ -1,5 +1,6 @@
 #![feature(test)]
 pub mod part1;
 pub mod part2;
+pub mod composition;
 
-mod common;
\ No newline at end of file
+pub mod common;
\ No newline at end of file
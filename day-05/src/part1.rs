@@ -1,6 +1,4 @@
-use std::collections::HashMap;
-
-use crate::common::{Category, Mapping, MappingCollection};
+use crate::common::{MappingCollection, MappingGraph};
 
 pub fn run(input: &str) -> String {
     let almanac = Almanac::parse(input);
@@ -11,7 +9,7 @@ pub fn run(input: &str) -> String {
 
 struct Almanac {
     seeds: Vec<u64>,
-    mappings: HashMap<Category, Mapping>
+    mappings: MappingGraph
 }
 
 
@@ -19,15 +17,15 @@ struct Almanac {
 impl Almanac {
     /// Parses the input and return an Almanac
     fn parse(input: &str) -> Almanac {
-        let mut elements = input.split("\n\n");
-        let seeds = elements.next().unwrap().split(": ").nth(1).unwrap().split(" ").map(|s| s.parse().unwrap()).collect();
-        let mappings = elements.map(|mapping_input| Mapping::parse(mapping_input)).map(|m| (m.source.clone(), m)).collect();
+        let (seeds_input, mappings_input) = input.split_once("\n\n").unwrap();
+        let seeds = seeds_input.split(": ").nth(1).unwrap().split(" ").map(|s| s.parse().unwrap()).collect();
+        let mappings = MappingGraph::parse(mappings_input).unwrap();
         Almanac { seeds, mappings }
     }
 }
 
 impl MappingCollection for Almanac {
-    fn get_mappings(&self) -> &HashMap<Category, Mapping> {
+    fn get_mappings(&self) -> &MappingGraph {
         &self.mappings
     }
 }
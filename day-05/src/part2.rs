@@ -1,6 +1,6 @@
-use std::{collections::HashMap, ops::Range};
+use std::ops::Range;
 
-use crate::common::{Category, Mapping, MappingCollection, MappingRange};
+use crate::common::{Category, Mapping, MappingCollection, MappingGraph, MappingRange};
 
 pub fn run(input: &str) -> String {
     let almanac= Almanac::parse(input);
@@ -18,44 +18,38 @@ pub fn run(input: &str) -> String {
 
 struct Almanac {
     seeds_ranges: Vec<Range<u64>>,
-    mappings: HashMap<Category, Mapping>
+    mappings: MappingGraph
 }
 
 impl Almanac {
     /// Parses the input and return an Almanac
     fn parse(input: &str) -> Almanac {
-        let mut elements = input.split("\n\n");
-        let seed_numbers: Vec<u64> = elements.next().unwrap().split(": ").nth(1).unwrap().split(" ").map(|s| s.parse().unwrap()).collect();
+        let (seeds_input, mappings_input) = input.split_once("\n\n").unwrap();
+        let seed_numbers: Vec<u64> = seeds_input.split(": ").nth(1).unwrap().split(" ").map(|s| s.parse().unwrap()).collect();
         let mut seeds_ranges = Vec::new();
         assert!(seed_numbers.len() % 2 == 0);
         for seed_pair_index in (0..seed_numbers.len()).step_by(2) {
             let seed_range = Range { start: seed_numbers[seed_pair_index], end: seed_numbers[seed_pair_index] + seed_numbers[seed_pair_index + 1] };
             seeds_ranges.push(seed_range);
         }
-        let mappings = elements.map(|mapping_input| Mapping::parse(mapping_input)).map(|m| (m.source.clone(), m)).collect();
+        let mappings = MappingGraph::parse(mappings_input).unwrap();
         Almanac { seeds_ranges, mappings }
     }
 
     fn map_seed_range_to_location_ranges(&self, seed_range: &Range<u64>) -> Vec<Range<u64>> {
         let mut mapped_ranges = vec![seed_range.clone()];
-        let mut category = &Category::Seed;
-        let mappings = self.get_mappings();
-        loop {
-            let mapping = mappings.get(&category).unwrap();
-            category = &mapping.destination;
+        let path = self.get_mappings().path(&Category::seed(), &Category::location()).unwrap();
+        for mapping in path {
             let new_mapped_ranges = mapped_ranges.iter().flat_map(|range| mapping.map_range(range)).collect();
             mapped_ranges = new_mapped_ranges;
             mapped_ranges.sort_by_key(|range|{range.start});
-            if category == &Category::Location {
-                break;
-            }
         }
         mapped_ranges
     }
 }
 
 impl MappingCollection for Almanac {
-    fn get_mappings(&self) -> &HashMap<Category, Mapping> {
+    fn get_mappings(&self) -> &MappingGraph {
         &self.mappings
     }
 }
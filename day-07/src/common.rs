@@ -1,3 +1,5 @@
+use crate::rules::RuleSet;
+
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub enum HandType {
@@ -5,6 +7,7 @@ pub enum HandType {
     Pair,
     TwoPair,
     ThreeOfAKind,
+    Straight,
     FullHouse,
     FourOfAKind,
     FiveOfAKind,
@@ -28,31 +31,21 @@ impl PartialEq for Hand {
     }
 }
 
+/// The strength of a card, as given by its position in the `RuleSet` card order
 pub type Card = u32;
 
-fn parse_card(card_input: char) -> Card {
-    match card_input {
-        'T' => 10,
-        'J' => 11,
-        'Q' => 12,
-        'K' => 13,
-        'A' => 14,
-        _ => card_input.to_digit(10).unwrap(),
-    }
-}
-
 impl Hand {
-    fn parse<F: FnOnce(&[Card]) -> HandType>(hand_input: &str, get_hand_type: F) -> Hand {
-        let cards: Vec<Card> = hand_input.chars().map(parse_card).collect();
-        let hand_type = get_hand_type(&cards);
+    fn parse(hand_input: &str, rules: &RuleSet) -> Hand {
+        let cards: Vec<Card> = hand_input.chars().map(|card| rules.parse_card(card).expect("Unknown card")).collect();
+        let hand_type = rules.get_hand_type(&cards);
         Hand { cards, hand_type }
     }
 }
 
 impl Bet {
-    pub fn parse<F: FnMut(&[Card]) -> HandType>(bet_input: &str, get_hand_type: F) -> Bet {
+    pub fn parse(bet_input: &str, rules: &RuleSet) -> Bet {
         let mut parts = bet_input.split_whitespace();
-        let hand = Hand::parse(parts.next().unwrap(), get_hand_type);
+        let hand = Hand::parse(parts.next().unwrap(), rules);
         let bid = parts.next().unwrap().parse().unwrap();
         Bet { hand, bid }
     }
@@ -1,56 +1,10 @@
-use core::panic;
-use std::{cmp::Ordering, collections::HashMap};
-
-use crate::common::{Bet, Card, Hand, HandType};
+use crate::rules::RuleSet;
 
 pub fn run(input: &str) -> String {
-    let mut bets: Vec<Bet> = input.lines().map(|line|Bet::parse(line, get_hand_type)).collect();
-    bets.sort_by(|a, b| compare_hands(&a.hand, &b.hand));
-    let result = bets
-        .iter()
-        .enumerate()
-        .map(|(bet_index, bet)| (bet_index + 1) as u32 * bet.bid)
-        .sum::<u32>();
+    let result = RuleSet::standard().total_winnings(input);
     result.to_string()
 }
 
-fn get_hand_type(cards: &[Card]) -> HandType {
-    let mut card_count_by_value: Vec<u32> = cards
-        .into_iter()
-        .fold(HashMap::new(), |mut acc, &card| {
-            *acc.entry(card).or_insert(0) += 1;
-            acc
-        })
-        .into_values()
-        .collect();
-    card_count_by_value.sort_by(|a, b| b.cmp(a));
-    match (card_count_by_value[0], card_count_by_value.get(1).unwrap_or(&0)) {
-        (5, 0) => HandType::FiveOfAKind,
-        (4, 1) => HandType::FourOfAKind,
-        (3, 2) => HandType::FullHouse,
-        (3, 1) => HandType::ThreeOfAKind,
-        (2, 2) => HandType::TwoPair,
-        (2, 1) => HandType::Pair,
-        (1, _) => HandType::HighCard,
-        _ => panic!("Invalid hand"),
-    }
-}
-
-fn compare_hands(first: &Hand, other: &Hand) -> Ordering {
-    match first.hand_type.cmp(&other.hand_type) {
-        Ordering::Equal => {
-            for (a, b) in first.cards.iter().zip(other.cards.iter()) {
-                match a.cmp(b) {
-                    Ordering::Equal => continue,
-                    x => return x,
-                }
-            }
-            Ordering::Equal
-        }
-        x => x,
-    }
-}
-
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,268 @@
+use std::{cmp::Ordering, collections::HashMap, fmt};
+
+use crate::common::{Bet, Card, Hand, HandType};
+
+/// How the type of a hand is determined
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Classifier {
+    /// Only the sizes of the groups of identical cards matter
+    Groups,
+    /// Like `Groups`, but five consecutive cards also make a straight
+    Straights,
+}
+
+/// How two hands of the same type are ordered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TieBreaker {
+    /// Compare the cards one by one in the order they were dealt
+    Positional,
+    /// Compare the biggest groups first, then the kickers, like in traditional poker
+    Grouped,
+}
+
+/// The rules used to parse and rank hands
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleSet {
+    /// The cards, from the weakest to the strongest
+    card_order: Vec<char>,
+    wildcards: Vec<char>,
+    classifier: Classifier,
+    tie_breaker: TieBreaker,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RuleSetError {
+    InvalidLine(String),
+    UnknownKey(String),
+    InvalidValue { key: String, value: String },
+    DuplicateCard(char),
+    UnknownWildcard(char),
+    MissingCards,
+}
+
+impl RuleSet {
+    pub fn new(card_order: &str, wildcards: &str, classifier: Classifier, tie_breaker: TieBreaker) -> Result<RuleSet, RuleSetError> {
+        let card_order: Vec<char> = card_order.chars().collect();
+        if card_order.is_empty() {
+            return Err(RuleSetError::MissingCards);
+        }
+        for (index, &card) in card_order.iter().enumerate() {
+            if card_order[..index].contains(&card) {
+                return Err(RuleSetError::DuplicateCard(card));
+            }
+        }
+        let wildcards: Vec<char> = wildcards.chars().collect();
+        if let Some(&wildcard) = wildcards.iter().find(|wildcard| !card_order.contains(wildcard)) {
+            return Err(RuleSetError::UnknownWildcard(wildcard));
+        }
+        Ok(RuleSet { card_order, wildcards, classifier, tie_breaker })
+    }
+
+    /// The rules of the first part of the puzzle
+    pub fn standard() -> RuleSet {
+        RuleSet::new("23456789TJQKA", "", Classifier::Groups, TieBreaker::Positional).unwrap()
+    }
+
+    /// The rules of the second part of the puzzle, where J is the weakest card but acts as a wildcard
+    pub fn jokers() -> RuleSet {
+        RuleSet::new("J23456789TQKA", "J", Classifier::Groups, TieBreaker::Positional).unwrap()
+    }
+
+    /// Parses a rule set from a config with one `key = value` per line.
+    /// The keys are `cards` (weakest first), `wildcards`, `classifier` (`groups` or `straights`)
+    /// and `tie_breaker` (`positional` or `grouped`). Only `cards` is required.
+    pub fn parse(config: &str) -> Result<RuleSet, RuleSetError> {
+        let mut card_order = None;
+        let mut wildcards = "";
+        let mut classifier = Classifier::Groups;
+        let mut tie_breaker = TieBreaker::Positional;
+        for line in config.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+            let (key, value) = line.split_once('=').ok_or_else(|| RuleSetError::InvalidLine(line.to_string()))?;
+            let (key, value) = (key.trim(), value.trim());
+            let invalid_value = || RuleSetError::InvalidValue { key: key.to_string(), value: value.to_string() };
+            match key {
+                "cards" => card_order = Some(value),
+                "wildcards" => wildcards = value,
+                "classifier" => {
+                    classifier = match value {
+                        "groups" => Classifier::Groups,
+                        "straights" => Classifier::Straights,
+                        _ => return Err(invalid_value()),
+                    }
+                }
+                "tie_breaker" => {
+                    tie_breaker = match value {
+                        "positional" => TieBreaker::Positional,
+                        "grouped" => TieBreaker::Grouped,
+                        _ => return Err(invalid_value()),
+                    }
+                }
+                _ => return Err(RuleSetError::UnknownKey(key.to_string())),
+            }
+        }
+        RuleSet::new(card_order.ok_or(RuleSetError::MissingCards)?, wildcards, classifier, tie_breaker)
+    }
+
+    /// Returns the strength of a card, or None if the card is not part of the rules
+    pub fn parse_card(&self, card_input: char) -> Option<Card> {
+        self.card_order.iter().position(|&card| card == card_input).map(|strength| strength as Card)
+    }
+
+    fn is_wildcard(&self, card: Card) -> bool {
+        self.wildcards.contains(&self.card_order[card as usize])
+    }
+
+    /// Determines the type of a hand, wildcards take whatever value makes the hand the strongest
+    pub fn get_hand_type(&self, cards: &[Card]) -> HandType {
+        let mut card_count_by_value: HashMap<Card, u32> = HashMap::new();
+        let mut wildcard_count = 0;
+        for &card in cards {
+            if self.is_wildcard(card) {
+                wildcard_count += 1;
+            } else {
+                *card_count_by_value.entry(card).or_insert(0) += 1;
+            }
+        }
+        let mut card_counts: Vec<u32> = card_count_by_value.values().copied().collect();
+        card_counts.sort_by(|a, b| b.cmp(a));
+        let first = card_counts.first().unwrap_or(&0) + wildcard_count;
+        let second = *card_counts.get(1).unwrap_or(&0);
+        let hand_type = match (first, second) {
+            (5.., _) => HandType::FiveOfAKind,
+            (4, _) => HandType::FourOfAKind,
+            (3, 2..) => HandType::FullHouse,
+            (3, _) => HandType::ThreeOfAKind,
+            (2, 2..) => HandType::TwoPair,
+            (2, _) => HandType::Pair,
+            _ => HandType::HighCard,
+        };
+        if self.classifier == Classifier::Straights && hand_type < HandType::Straight && self.is_straight(&card_count_by_value, wildcard_count) {
+            HandType::Straight
+        } else {
+            hand_type
+        }
+    }
+
+    /// A straight is made of five distinct consecutive cards, wildcards can fill the gaps.
+    /// Wildcards are left out of the order when checking that cards are consecutive.
+    fn is_straight(&self, card_count_by_value: &HashMap<Card, u32>, wildcard_count: u32) -> bool {
+        let card_count = card_count_by_value.len() as u32 + wildcard_count;
+        if card_count != 5 || card_count_by_value.values().any(|&count| count > 1) {
+            return false;
+        }
+        let ranks: Vec<usize> = card_count_by_value
+            .keys()
+            .map(|&card| (0..card as usize).filter(|&index| !self.wildcards.contains(&self.card_order[index])).count())
+            .collect();
+        match (ranks.iter().min(), ranks.iter().max()) {
+            (Some(min), Some(max)) => max - min < 5,
+            _ => true,
+        }
+    }
+
+    /// Orders two hands, the weakest first
+    pub fn compare_hands(&self, first: &Hand, other: &Hand) -> Ordering {
+        first.hand_type.cmp(&other.hand_type).then_with(|| match self.tie_breaker {
+            TieBreaker::Positional => first.cards.cmp(&other.cards),
+            TieBreaker::Grouped => grouped_cards(&first.cards).cmp(&grouped_cards(&other.cards)),
+        })
+    }
+
+    /// Ranks the bets and returns the sum of each bid multiplied by its rank
+    pub fn total_winnings(&self, input: &str) -> u32 {
+        let mut bets: Vec<Bet> = input.lines().map(|line| Bet::parse(line, self)).collect();
+        bets.sort_by(|a, b| self.compare_hands(&a.hand, &b.hand));
+        bets.iter().enumerate().map(|(bet_index, bet)| (bet_index + 1) as u32 * bet.bid).sum()
+    }
+}
+
+/// Sorts the cards by group size and then by strength, the strongest first
+fn grouped_cards(cards: &[Card]) -> Vec<Card> {
+    let mut card_count_by_value: HashMap<Card, u32> = HashMap::new();
+    for &card in cards {
+        *card_count_by_value.entry(card).or_insert(0) += 1;
+    }
+    let mut grouped = cards.to_vec();
+    grouped.sort_by(|a, b| card_count_by_value[b].cmp(&card_count_by_value[a]).then(b.cmp(a)));
+    grouped
+}
+
+impl fmt::Display for RuleSetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleSetError::InvalidLine(line) => write!(f, "Invalid line: {}", line),
+            RuleSetError::UnknownKey(key) => write!(f, "Unknown key: {}", key),
+            RuleSetError::InvalidValue { key, value } => write!(f, "Invalid value for {}: {}", key, value),
+            RuleSetError::DuplicateCard(card) => write!(f, "Card {} appears twice in the order", card),
+            RuleSetError::UnknownWildcard(card) => write!(f, "Wildcard {} is not in the card order", card),
+            RuleSetError::MissingCards => write!(f, "No card order was provided"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hand_type(rules: &RuleSet, hand: &str) -> HandType {
+        Bet::parse(&format!("{} 1", hand), rules).hand.hand_type
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let config = "# part 2 rules
+cards = J23456789TQKA
+wildcards = J
+classifier = groups
+tie_breaker = positional";
+        assert_eq!(RuleSet::parse(config), Ok(RuleSet::jokers()));
+        assert_eq!(RuleSet::parse("cards = 23456789TJQKA"), Ok(RuleSet::standard()));
+        assert_eq!(RuleSet::parse("wildcards = J"), Err(RuleSetError::MissingCards));
+        assert_eq!(RuleSet::parse("cards = 2345\nwildcards = J"), Err(RuleSetError::UnknownWildcard('J')));
+        assert_eq!(RuleSet::parse("cards = 2342"), Err(RuleSetError::DuplicateCard('2')));
+        assert_eq!(
+            RuleSet::parse("cards = 2345\nclassifier = poker"),
+            Err(RuleSetError::InvalidValue { key: "classifier".to_string(), value: "poker".to_string() })
+        );
+    }
+
+    #[test]
+    fn test_multiple_wildcards() {
+        let rules = RuleSet::parse("cards = 23456789TJQKA\nwildcards = 2J").unwrap();
+        assert_eq!(hand_type(&rules, "2J2AK"), HandType::FourOfAKind);
+        assert_eq!(hand_type(&rules, "2JJ22"), HandType::FiveOfAKind);
+        assert_eq!(hand_type(&rules, "2AAKK"), HandType::FullHouse);
+    }
+
+    #[test]
+    fn test_straights() {
+        let rules = RuleSet::parse("cards = 23456789TJQKA\nclassifier = straights\ntie_breaker = grouped").unwrap();
+        assert_eq!(hand_type(&rules, "9TJQK"), HandType::Straight);
+        assert_eq!(hand_type(&rules, "9TJQA"), HandType::HighCard);
+        assert_eq!(hand_type(&RuleSet::standard(), "9TJQK"), HandType::HighCard);
+        let wild = RuleSet::parse("cards = J23456789TQKA\nwildcards = J\nclassifier = straights").unwrap();
+        assert_eq!(hand_type(&wild, "9TJQK"), HandType::Straight);
+        assert_eq!(hand_type(&wild, "8TJQK"), HandType::Straight);
+        assert_eq!(hand_type(&wild, "7TJQK"), HandType::Pair);
+    }
+
+    #[test]
+    fn test_grouped_tie_breaker() {
+        let rules = RuleSet::parse("cards = 23456789TJQKA\ntie_breaker = grouped").unwrap();
+        let low_pair = Bet::parse("A2K23 1", &rules).hand;
+        let high_pair = Bet::parse("3KKA4 1", &rules).hand;
+        assert_eq!(rules.compare_hands(&low_pair, &high_pair), Ordering::Less);
+        assert_eq!(RuleSet::standard().compare_hands(&low_pair, &high_pair), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_total_winnings() {
+        let input = "32T3K 765
+T55J5 684
+KK677 28
+KTJJT 220
+QQQJA 483";
+        assert_eq!(RuleSet::standard().total_winnings(input), 6440);
+        assert_eq!(RuleSet::jokers().total_winnings(input), 5905);
+    }
+}
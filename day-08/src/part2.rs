@@ -1,60 +1,98 @@
-use std::collections::HashSet;
+use std::collections::HashMap;
 
-use crate::common::{self, Instruction, NodeKey};
-use num::integer;
+use crate::common::{self, Graph, Instruction, NodeKey};
+use num::integer::{self, Integer};
 
 pub fn run(input: &str) -> String {
     let (instructions, graph) = common::parse_instructions_and_graphs(input);
     let starting_nodes = graph.nodes.keys().filter(|key| key.ends_with("A")).collect::<Vec<&String>>();
-    let periods: Vec<usize> = starting_nodes.iter().map(|starting_node| find_ending_state_period(starting_node, &instructions, &graph)).collect();
-    let result = periods.iter().fold(1, |acc, period| integer::lcm(acc, *period));
-    return result.to_string();
+    let walks: Vec<Walk> = starting_nodes.iter().map(|starting_node| Walk::explore(starting_node, &instructions, &graph)).collect();
+    let result = first_common_ending(&walks).expect("The ghosts never reach ending nodes at the same time");
+    result.to_string()
 }
 
-#[derive(Debug, Eq, Hash)]
-struct EndingState {
-    instructions_length: usize,
-    instruction_index: usize,
-    node: NodeKey,
+/// The structure of the walk of a ghost over the states (node, instruction index).
+/// After `prefix_length` steps the walk enters a cycle of `cycle_length` steps.
+/// The ghost is on an ending node after each step count in `prefix_hits`,
+/// and after each step count `hit + k * cycle_length` for `hit` in `cycle_hits`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Walk {
+    pub prefix_length: u64,
+    pub cycle_length: u64,
+    pub prefix_hits: Vec<u64>,
+    pub cycle_hits: Vec<u64>,
 }
 
-impl PartialEq for EndingState {
-    fn eq(&self, other: &Self) -> bool {
-        if self.node != other.node {
-            return false;
+impl Walk {
+    pub fn explore(starting_node: &NodeKey, instructions: &[Instruction], graph: &Graph) -> Walk {
+        let instructions_length = instructions.len();
+        let mut first_visits: HashMap<(&NodeKey, usize), u64> = HashMap::new();
+        let mut hits = Vec::new();
+        let mut current_node = starting_node;
+        let mut step = 0;
+        loop {
+            let instruction_index = step as usize % instructions_length;
+            if let Some(&cycle_start) = first_visits.get(&(current_node, instruction_index)) {
+                let (prefix_hits, cycle_hits) = hits.iter().partition(|&&hit| hit < cycle_start);
+                return Walk { prefix_length: cycle_start, cycle_length: step - cycle_start, prefix_hits, cycle_hits };
+            }
+            first_visits.insert((current_node, instruction_index), step);
+            if current_node.ends_with("Z") {
+                hits.push(step);
+            }
+            current_node = match instructions[instruction_index] {
+                Instruction::Left => &graph.nodes[current_node].left,
+                Instruction::Right => &graph.nodes[current_node].right,
+            };
+            step += 1;
         }
-        let diff = self.instruction_index as isize - other.instruction_index as isize;
-        return diff.abs() as usize % self.instructions_length == 0;
     }
-}
 
-fn find_ending_state_period(starting_node: & NodeKey, instructions: &Vec<Instruction>, graph: & common::Graph) -> usize {
-    let mut current_node = starting_node;
-    let mut instruction_index = 0;
-    let instructions_length = instructions.len();
-    let mut ending_states = HashSet::new();
-    loop {
-        let instruction = &instructions[instruction_index % instructions_length];
-        current_node = match instruction {
-            Instruction::Left => &graph.nodes[current_node].left,
-            Instruction::Right => &graph.nodes[current_node].right,
-        };
-        if current_node.ends_with("Z") {
-            let ending_state = EndingState {
-                instructions_length,
-                instruction_index,
-                node: current_node.clone(),
-            };
-            if ending_states.contains(&ending_state) {
-                break;
-            }
-            ending_states.insert(ending_state);
+    fn is_hit(&self, step: u64) -> bool {
+        if step < self.prefix_length {
+            self.prefix_hits.contains(&step)
+        } else {
+            self.cycle_hits.iter().any(|&hit| step >= hit && (step - hit).is_multiple_of(self.cycle_length))
         }
-        instruction_index += 1;
     }
-    let mut ending_states = ending_states.into_iter().collect::<Vec<EndingState>>();
-    ending_states.sort_by_key(|ending_state| ending_state.instruction_index);
-    ending_states[1].instruction_index - ending_states[0].instruction_index
+}
+
+/// Finds the first step count after which every walk is on an ending node
+pub fn first_common_ending(walks: &[Walk]) -> Option<u64> {
+    let longest_prefix = walks.iter().map(|walk| walk.prefix_length).max()?;
+    let longest_walk = walks.iter().find(|walk| walk.prefix_length == longest_prefix)?;
+    // Before the longest prefix ends, the step must be one of its prefix hits
+    if let Some(&step) = longest_walk.prefix_hits.iter().find(|&&step| walks.iter().all(|walk| walk.is_hit(step))) {
+        return Some(step);
+    }
+    // Afterwards every walk is periodic, so the residues can be combined with the chinese remainder theorem
+    let mut congruences = vec![(0, 1)];
+    for walk in walks {
+        congruences = congruences
+            .iter()
+            .flat_map(|&congruence| walk.cycle_hits.iter().filter_map(move |&hit| combine(congruence, (hit % walk.cycle_length, walk.cycle_length))))
+            .collect();
+        congruences.sort();
+        congruences.dedup();
+    }
+    congruences
+        .into_iter()
+        .map(|(residue, modulus)| if residue >= longest_prefix { residue } else { residue + (longest_prefix - residue).div_ceil(modulus) * modulus })
+        .min()
+}
+
+/// Solves the system x = a (mod n), x = b (mod m), returning x modulo lcm(n, m)
+fn combine((a, n): (u64, u64), (b, m): (u64, u64)) -> Option<(u64, u64)> {
+    let (a, n, b, m) = (a as i128, n as i128, b as i128, m as i128);
+    let extended_gcd = n.extended_gcd(&m);
+    let gcd = extended_gcd.gcd;
+    if (b - a) % gcd != 0 {
+        return None;
+    }
+    let lcm = integer::lcm(n, m);
+    let x = (a + n * ((b - a) / gcd * extended_gcd.x).rem_euclid(m / gcd)).rem_euclid(lcm);
+    let lcm = u64::try_from(lcm).expect("The lcm of the cycles overflows u64");
+    Some((x as u64, lcm))
 }
 
 #[cfg(test)]
@@ -77,5 +115,48 @@ XXX = (XXX, XXX)"; // Add your test input here
         assert_eq!(run(input), expected_output);
     }
 
-    // Add more tests here
+    #[test]
+    fn test_multiple_ending_offsets() {
+        let input = "L
+
+1A = (1BZ, 1BZ)
+1BZ = (1C, 1C)
+1C = (1DZ, 1DZ)
+1DZ = (1E, 1E)
+1E = (1F, 1F)
+1F = (1BZ, 1BZ)
+2A = (2B, 2B)
+2B = (2CZ, 2CZ)
+2CZ = (2D, 2D)
+2D = (2B, 2B)";
+        let (instructions, graph) = common::parse_instructions_and_graphs(input);
+        let walk = Walk::explore(&"1A".to_string(), &instructions, &graph);
+        assert_eq!(walk, Walk { prefix_length: 1, cycle_length: 5, prefix_hits: vec![], cycle_hits: vec![1, 3] });
+        assert_eq!(run(input), "8");
+    }
+
+    #[test]
+    fn test_ending_before_cycle() {
+        let input = "L
+
+1A = (1BZ, 1BZ)
+1BZ = (1C, 1C)
+1C = (1C, 1C)
+2A = (2BZ, 2BZ)
+2BZ = (2C, 2C)
+2C = (2BZ, 2BZ)";
+        let (instructions, graph) = common::parse_instructions_and_graphs(input);
+        let walk = Walk::explore(&"1A".to_string(), &instructions, &graph);
+        assert_eq!(walk, Walk { prefix_length: 2, cycle_length: 1, prefix_hits: vec![1], cycle_hits: vec![] });
+        assert_eq!(run(input), "1");
+    }
+
+    #[test]
+    fn test_never_common() {
+        let walks = vec![
+            Walk { prefix_length: 0, cycle_length: 2, prefix_hits: vec![], cycle_hits: vec![0] },
+            Walk { prefix_length: 0, cycle_length: 4, prefix_hits: vec![], cycle_hits: vec![1, 3] },
+        ];
+        assert_eq!(first_common_ending(&walks), None);
+    }
 }
\ No newline at end of file
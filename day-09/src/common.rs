@@ -1,5 +1,21 @@
+use std::fmt;
+
 pub struct History {
-    pub values: Vec<i32>,
+    pub values: Vec<i128>,
+}
+
+/// The polynomial of minimal degree going through the values of a history,
+/// stored as its Newton forward-difference coefficients: p(x) = sum of c_k * binomial(x, k)
+#[derive(Debug, PartialEq, Eq)]
+pub struct Polynomial {
+    coefficients: Vec<i128>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum HistoryError {
+    /// The differences never become constant with the available values
+    NotPolynomial,
+    Overflow,
 }
 
 impl History {
@@ -10,4 +26,86 @@ impl History {
             .collect();
         Self { values }
     }
-}
\ No newline at end of file
+
+    /// Finds the polynomial of minimal degree matching the history.
+    /// A degree is only accepted if its differences are constant over at least two values.
+    pub fn polynomial(&self) -> Result<Polynomial, HistoryError> {
+        let mut coefficients = Vec::new();
+        let mut diffs = self.values.clone();
+        while diffs.len() >= 2 {
+            coefficients.push(diffs[0]);
+            if diffs.iter().all(|&d| d == diffs[0]) {
+                return Ok(Polynomial { coefficients });
+            }
+            diffs = diffs
+                .windows(2)
+                .map(|w| w[1].checked_sub(w[0]))
+                .collect::<Option<Vec<i128>>>()
+                .ok_or(HistoryError::Overflow)?;
+        }
+        Err(HistoryError::NotPolynomial)
+    }
+
+    /// Extrapolates the history by `steps` values, after the last value if `steps` is positive,
+    /// or before the first one if it is negative
+    pub fn extrapolate(&self, steps: i64) -> Result<i128, HistoryError> {
+        let index = if steps >= 0 { self.values.len() as i128 - 1 + steps as i128 } else { steps as i128 };
+        self.polynomial()?.evaluate(index).ok_or(HistoryError::Overflow)
+    }
+}
+
+impl Polynomial {
+    pub fn degree(&self) -> usize {
+        self.coefficients.len() - 1
+    }
+
+    /// Evaluates the polynomial at an index, 0 being the index of the first value of the history.
+    /// Returns None if the computation overflows.
+    pub fn evaluate(&self, index: i128) -> Option<i128> {
+        let mut value: i128 = 0;
+        let mut binomial: i128 = 1;
+        for (k, &coefficient) in self.coefficients.iter().enumerate() {
+            value = value.checked_add(coefficient.checked_mul(binomial)?)?;
+            // binomial(x, k + 1) = binomial(x, k) * (x - k) / (k + 1), the division is always exact
+            binomial = binomial.checked_mul(index - k as i128)? / (k as i128 + 1);
+        }
+        Some(value)
+    }
+}
+
+impl fmt::Display for HistoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HistoryError::NotPolynomial => write!(f, "The history is not polynomial within the available values"),
+            HistoryError::Overflow => write!(f, "The computation overflowed"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_degree() {
+        assert_eq!(History::parse("0 3 6 9 12 15").polynomial().unwrap().degree(), 1);
+        assert_eq!(History::parse("1 3 6 10 15 21").polynomial().unwrap().degree(), 2);
+        assert_eq!(History::parse("7 7 7").polynomial().unwrap().degree(), 0);
+        assert_eq!(History::parse("1 2 4 8 16").polynomial(), Err(HistoryError::NotPolynomial));
+        assert_eq!(History::parse("5").polynomial(), Err(HistoryError::NotPolynomial));
+        assert_eq!(History::parse("1 3").extrapolate(1), Err(HistoryError::NotPolynomial));
+    }
+
+    #[test]
+    fn test_extrapolate_many_steps() {
+        let history = History::parse("1 3 6 10 15 21");
+        // The triangular numbers, value at index i is (i + 1)(i + 2) / 2
+        assert_eq!(history.extrapolate(1), Ok(28));
+        assert_eq!(history.extrapolate(1000), Ok(1006 * 1007 / 2));
+        assert_eq!(history.extrapolate(-1), Ok(0));
+        assert_eq!(history.extrapolate(-5), Ok(6));
+        let polynomial = history.polynomial().unwrap();
+        assert_eq!(polynomial.evaluate(2), Some(6));
+        assert_eq!(polynomial.evaluate(i128::MAX), None);
+    }
+}
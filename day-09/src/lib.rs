@@ -1,4 +1,4 @@
 pub mod part1;
 pub mod part2;
 
-mod common;
\ No newline at end of file
+pub mod common;
\ No newline at end of file
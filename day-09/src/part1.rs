@@ -2,20 +2,8 @@ use crate::common::History;
 
 pub fn run(input: &str) -> String {
     let histories = input.lines().map(History::parse);
-    let extrapolated = histories.map(|h| h.extrapolate());
-    extrapolated.sum::<i32>().to_string()
-}
-
-impl History {
-    fn extrapolate(&self) -> i32 {
-        let mut diffs = self.values.clone();
-        let mut last_values = Vec::new();
-        while !diffs.iter().all(|&d| d == 0) {
-            last_values.push(*diffs.last().unwrap());
-            diffs = diffs.windows(2).map(|w| w[1] - w[0]).collect();
-        }
-        last_values.iter().sum()
-    }
+    let extrapolated = histories.map(|h| h.extrapolate(1).expect("Couldn't extrapolate the history"));
+    extrapolated.sum::<i128>().to_string()
 }
 
 #[cfg(test)]
@@ -1,26 +1,9 @@
 use crate::common::History;
 
 pub fn run(input: &str) -> String {
-    let histories = input.lines().map(History::parse).collect::<Vec<History>>();
-    let extrapolated = histories.iter().map(|h| h.extrapolate_backward()).collect::<Vec<i32>>();
-    extrapolated.iter().sum::<i32>().to_string()
-}
-
-impl History {
-    fn extrapolate_backward(&self) -> i32 {
-        let mut diffs = self.values.clone();
-        let mut first_values = Vec::new();
-        while !diffs.iter().all(|&d| d == 0) {
-            first_values.push(diffs[0]);
-            diffs = diffs.windows(2).map(|w| w[1] - w[0]).collect();
-        }
-        first_values.reverse();
-        let extrapolated = first_values
-        .iter()
-        .fold(0, |acc, &value| value - acc);
-        extrapolated
-    }
-
+    let histories = input.lines().map(History::parse);
+    let extrapolated = histories.map(|h| h.extrapolate(-1).expect("Couldn't extrapolate the history"));
+    extrapolated.sum::<i128>().to_string()
 }
 
 #[cfg(test)]
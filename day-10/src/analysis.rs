@@ -0,0 +1,153 @@
+use std::collections::{hash_map::Entry, HashMap};
+
+use crate::common::{Direction, Grid, Position, Tile, TileValue, ALL_DIRECTIONS};
+
+/// A group of pipes connected to each other
+#[derive(Debug, PartialEq)]
+pub struct Component {
+    pub positions: Vec<Position>,
+    /// The pipe ends that are not connected to a matching pipe
+    pub open_ends: Vec<(Position, Direction)>,
+    /// Whether the pipes form a closed loop, every pipe being connected on both ends
+    pub is_loop: bool,
+}
+
+/// Every pipe of a grid, grouped in connected components.
+/// Two neighboring pipes are connected if both have an end facing the other one,
+/// the start tile connects to any neighbor facing it.
+pub struct PipeNetwork {
+    pub components: Vec<Component>,
+}
+
+impl PipeNetwork {
+    pub fn loops(&self) -> impl Iterator<Item = &Component> {
+        self.components.iter().filter(|component| component.is_loop)
+    }
+
+    /// The components that are not closed loops
+    pub fn dangling(&self) -> impl Iterator<Item = &Component> {
+        self.components.iter().filter(|component| !component.is_loop)
+    }
+}
+
+impl Tile {
+    fn is_pipe(&self) -> bool {
+        self.value != TileValue::Empty
+    }
+
+    fn ends(&self) -> Vec<Direction> {
+        match self.value {
+            TileValue::Pipe(direction1, direction2) => vec![direction1, direction2],
+            TileValue::Start => ALL_DIRECTIONS.to_vec(),
+            TileValue::Empty => Vec::new(),
+        }
+    }
+}
+
+impl Grid {
+    /// The neighbors connected to the tile, and the ends of the tile that are not connected
+    fn connections(&self, tile: &Tile) -> (Vec<&Tile>, Vec<Direction>) {
+        let mut connected = Vec::new();
+        let mut open_ends = Vec::new();
+        for direction in tile.ends() {
+            match self.get_tile_in_direction(tile.position, &direction) {
+                Some(neighbor) if neighbor.ends().contains(&direction.opposite()) => connected.push(neighbor),
+                _ if tile.value != TileValue::Start => open_ends.push(direction),
+                _ => {}
+            }
+        }
+        (connected, open_ends)
+    }
+
+    pub fn pipe_network(&self) -> PipeNetwork {
+        let mut component_of: HashMap<Position, usize> = HashMap::new();
+        let mut components = Vec::new();
+        for tile in self.tiles.iter().flatten().filter(|tile| tile.is_pipe()) {
+            if component_of.contains_key(&tile.position) {
+                continue;
+            }
+            let mut component = Component { positions: Vec::new(), open_ends: Vec::new(), is_loop: true };
+            component_of.insert(tile.position, components.len());
+            let mut stack = vec![tile];
+            while let Some(current) = stack.pop() {
+                let (connected, open_ends) = self.connections(current);
+                component.is_loop &= connected.len() == 2 && open_ends.is_empty();
+                component.positions.push(current.position);
+                component.open_ends.extend(open_ends.into_iter().map(|direction| (current.position, direction)));
+                for neighbor in connected {
+                    if let Entry::Vacant(entry) = component_of.entry(neighbor.position) {
+                        entry.insert(components.len());
+                        stack.push(neighbor);
+                    }
+                }
+            }
+            component.positions.sort_by_key(|&(x, y)| (y, x));
+            component.open_ends.sort_by_key(|&((x, y), _)| (y, x));
+            components.push(component);
+        }
+        PipeNetwork { components }
+    }
+
+    /// A copy of the grid where every pipe that is not part of a closed loop is replaced with ground
+    pub fn cleaned(&self) -> Grid {
+        let network = self.pipe_network();
+        let mut looped = vec![vec![false; self.tiles.first().map_or(0, |row| row.len())]; self.tiles.len()];
+        for &(x, y) in network.loops().flat_map(|component| &component.positions) {
+            looped[y][x] = true;
+        }
+        let tiles = self
+            .tiles
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|tile| {
+                        let (x, y) = tile.position;
+                        let value = if looped[y][x] { tile.value.clone() } else { TileValue::Empty };
+                        Tile { value, position: tile.position }
+                    })
+                    .collect()
+            })
+            .collect();
+        let start_tile_position = self.start_tile_position.filter(|&(x, y)| looped[y][x]);
+        Grid { start_tile_position, tiles }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = "F7..
+LJ-7
+..|.";
+
+    #[test]
+    fn test_pipe_network() {
+        let grid = Grid::parse(INPUT);
+        let network = grid.pipe_network();
+        assert_eq!(network.components.len(), 3);
+        let loops: Vec<&Component> = network.loops().collect();
+        assert_eq!(loops.len(), 1);
+        assert_eq!(loops[0].positions, vec![(0, 0), (1, 0), (0, 1), (1, 1)]);
+        let dangling: Vec<&Component> = network.dangling().collect();
+        assert_eq!(dangling[0].open_ends, vec![((2, 1), Direction::West), ((3, 1), Direction::South)]);
+        assert_eq!(dangling[1].open_ends, vec![((2, 2), Direction::North), ((2, 2), Direction::South)]);
+    }
+
+    #[test]
+    fn test_start_in_loop() {
+        let grid = Grid::parse("..S-7
+-.|.|
+..L-J");
+        let network = grid.pipe_network();
+        assert_eq!(network.loops().count(), 1);
+        assert_eq!(network.loops().next().unwrap().positions.len(), 8);
+        assert_eq!(grid.cleaned().to_string(), "..S-7\n..|.|\n..L-J\n");
+    }
+
+    #[test]
+    fn test_cleaned() {
+        let grid = Grid::parse(INPUT);
+        assert_eq!(grid.cleaned().to_string(), "F7..\nLJ..\n....\n");
+    }
+}
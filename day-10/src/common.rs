@@ -1,5 +1,5 @@
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
 pub enum TileValue {
     Empty,
     Start,
@@ -14,7 +14,7 @@ pub enum Direction {
     West,
 }
 
-const ALL_DIRECTIONS: [Direction; 4] = [Direction::North, Direction::South, Direction::East, Direction::West];
+pub const ALL_DIRECTIONS: [Direction; 4] = [Direction::North, Direction::South, Direction::East, Direction::West];
 
 #[derive(Debug)]
 pub struct Tile {
@@ -25,10 +25,28 @@ pub struct Tile {
 pub type Position = (usize, usize);
 
 pub struct Grid {
-    pub start_tile_position: Position,
+    pub start_tile_position: Option<Position>,
     pub tiles: Vec<Vec<Tile>>,
 }
 
+/// The loop going through the start tile
+#[derive(Debug, PartialEq)]
+pub struct MainLoop {
+    /// The positions of the loop in walking order, starting with the start tile
+    pub positions: Vec<Position>,
+    /// The pipe hidden under the start tile
+    pub start_value: TileValue,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum LoopError {
+    NoStart,
+    /// The loop leaves the grid or reaches a tile that doesn't connect back
+    Broken { position: Position, direction: Direction },
+    /// Several loops go through the start tile, so its shape can't be inferred
+    Ambiguous { shapes: Vec<TileValue> },
+}
+
 impl TileValue {
     fn parse(tile: &char) -> Self {
         match tile {
@@ -66,7 +84,7 @@ impl Grid {
             tiles.push(row);
         }
         Grid {
-            start_tile_position: start_tile.unwrap(),
+            start_tile_position: start_tile,
             tiles,
         }
     }
@@ -75,7 +93,7 @@ impl Grid {
         self.tiles.get(position.1)?.get(position.0)
     }
 
-    fn get_tile_in_direction(&self, position: Position, direction: &Direction) -> Option<&Tile> {
+    pub fn get_tile_in_direction(&self, position: Position, direction: &Direction) -> Option<&Tile> {
         match direction {
             Direction::North => if position.1 > 0 { self.get_tile((position.0, position.1 - 1)) } else { None },
             Direction::South => self.get_tile((position.0, position.1 + 1)),
@@ -86,8 +104,24 @@ impl Grid {
 
 }
 
+impl TileValue {
+    /// The pipe connecting two directions, written in the same order as `parse`
+    pub fn from_directions(direction1: Direction, direction2: Direction) -> Self {
+        let rank = |direction: &Direction| ALL_DIRECTIONS.iter().position(|d| d == direction).unwrap();
+        if rank(&direction1) <= rank(&direction2) {
+            TileValue::Pipe(direction1, direction2)
+        } else {
+            TileValue::Pipe(direction2, direction1)
+        }
+    }
+
+    pub fn connects(&self, direction: Direction) -> bool {
+        matches!(self, TileValue::Pipe(direction1, direction2) if *direction1 == direction || *direction2 == direction)
+    }
+}
+
 impl Direction {
-    fn opposite(&self) -> Self {
+    pub fn opposite(&self) -> Self {
         match self {
             Direction::North => Direction::South,
             Direction::South => Direction::North,
@@ -98,56 +132,110 @@ impl Direction {
 }
 
 impl Tile {
-    fn get_next_direction(&self, current_direction: Direction) -> Direction {
+    fn get_next_direction(&self, current_direction: Direction) -> Option<Direction> {
         let previous_opposite = current_direction.opposite();
-        if let TileValue::Pipe(direction1, direction2) = &self.value {
-            if *direction1 == previous_opposite {
-                return *direction2;
-            } 
-            if *direction2 == previous_opposite {
-                return *direction1;
-            } 
-            panic!("Not a valid pipe for this origin direction {:?}", &self);
+        match self.value {
+            TileValue::Pipe(direction1, direction2) if direction1 == previous_opposite => Some(direction2),
+            TileValue::Pipe(direction1, direction2) if direction2 == previous_opposite => Some(direction1),
+            _ => None,
         }
-        panic!("Not a pipe {:?}", &self);
     }
 }
 
 impl Grid {
-    pub fn find_main_loop(&self) -> (Vec<&Tile>, Tile) {
-        
-        let start_tile = self.get_tile(self.start_tile_position).unwrap();
-
-        let mut main_loop = vec![start_tile];
+    /// Follows the loop going through the start tile, inferring the shape of the start tile
+    /// from the neighbors that connect back to it
+    pub fn find_main_loop(&self) -> Result<MainLoop, LoopError> {
+        let start_position = self.start_tile_position.ok_or(LoopError::NoStart)?;
+        let connected_directions: Vec<Direction> = ALL_DIRECTIONS
+            .into_iter()
+            .filter(|direction| {
+                self.get_tile_in_direction(start_position, direction)
+                    .is_some_and(|neighbor| neighbor.value.connects(direction.opposite()))
+            })
+            .collect();
+        let mut loops = Vec::new();
+        let mut first_error = None;
+        for (index, &start_direction) in connected_directions.iter().enumerate() {
+            for &end_direction in &connected_directions[index + 1..] {
+                match self.follow_loop(start_position, start_direction, end_direction) {
+                    Ok(positions) => loops.push(MainLoop { positions, start_value: TileValue::from_directions(start_direction, end_direction) }),
+                    Err(error) => {
+                        first_error.get_or_insert(error);
+                    }
+                }
+            }
+        }
+        match loops.len() {
+            0 => Err(first_error.unwrap_or(LoopError::Broken { position: start_position, direction: *connected_directions.first().unwrap_or(&Direction::North) })),
+            1 => Ok(loops.pop().unwrap()),
+            _ => Err(LoopError::Ambiguous { shapes: loops.into_iter().map(|main_loop| main_loop.start_value).collect() }),
+        }
+    }
 
-        let  (mut current_tile, mut current_direction) = self.find_start_neighbor();
-        let start_direction = current_direction;
+    /// Walks from the start tile towards `start_direction`, and checks that the walk
+    /// comes back to the start tile from `end_direction`
+    fn follow_loop(&self, start_position: Position, start_direction: Direction, end_direction: Direction) -> Result<Vec<Position>, LoopError> {
+        let mut positions = vec![start_position];
+        let mut current_position = start_position;
+        let mut current_direction = start_direction;
         loop {
-            main_loop.push(current_tile);
-            let next_direction = current_tile.get_next_direction(current_direction);
-            let next_tile = self.get_tile_in_direction(current_tile.position, &next_direction).unwrap();
-            current_tile = next_tile;
-            current_direction = next_direction;
+            let broken = LoopError::Broken { position: current_position, direction: current_direction };
+            let next_tile = self.get_tile_in_direction(current_position, &current_direction).ok_or(broken)?;
             if next_tile.value == TileValue::Start {
-                break;
+                return if current_direction.opposite() == end_direction {
+                    Ok(positions)
+                } else {
+                    Err(LoopError::Broken { position: current_position, direction: current_direction })
+                };
             }
+            current_direction = next_tile
+                .get_next_direction(current_direction)
+                .ok_or(LoopError::Broken { position: current_position, direction: current_direction })?;
+            current_position = next_tile.position;
+            positions.push(current_position);
         }
-        let start_actual_tile = Tile { value: TileValue::Pipe(start_direction, current_direction.opposite()), ..*start_tile};
-        (main_loop, start_actual_tile)
+    }
+}
+
+impl MainLoop {
+    /// The area of the polygon drawn by the centers of the loop tiles, with the shoelace formula
+    pub fn area(&self) -> usize {
+        let twice_area: i64 = self
+            .positions
+            .iter()
+            .zip(self.positions.iter().cycle().skip(1))
+            .map(|(&(x1, y1), &(x2, y2))| x1 as i64 * y2 as i64 - x2 as i64 * y1 as i64)
+            .sum();
+        twice_area.unsigned_abs() as usize / 2
     }
 
-    fn find_start_neighbor(&self) -> (&Tile, Direction) {
-        for direction in ALL_DIRECTIONS {
-            if let Some(neighbor_tile) = self.get_tile_in_direction(self.start_tile_position, &direction){
-                if let TileValue::Pipe(direction1, direction2) = neighbor_tile.value {
-                    let oposite_direction = direction.opposite();
-                    if direction1 == oposite_direction || direction2 == oposite_direction {
-                        return (neighbor_tile, direction);
+    /// The number of tiles enclosed by the loop, with Pick's theorem: area = inside + boundary / 2 - 1
+    pub fn enclosed_count(&self) -> usize {
+        self.area() + 1 - self.positions.len() / 2
+    }
+
+    /// The tiles enclosed by the loop, scanning each row once and counting the crossed pipes going north
+    pub fn enclosed_tiles(&self, grid: &Grid) -> Vec<Position> {
+        let mut in_loop = vec![vec![false; grid.tiles.first().map_or(0, |row| row.len())]; grid.tiles.len()];
+        for &(x, y) in &self.positions {
+            in_loop[y][x] = true;
+        }
+        let mut enclosed_tiles = Vec::new();
+        for (y, row) in grid.tiles.iter().enumerate() {
+            let mut inside = false;
+            for (x, tile) in row.iter().enumerate() {
+                if in_loop[y][x] {
+                    let value = if tile.value == TileValue::Start { &self.start_value } else { &tile.value };
+                    if value.connects(Direction::North) {
+                        inside = !inside;
                     }
+                } else if inside {
+                    enclosed_tiles.push((x, y));
                 }
             }
         }
-        panic!("No neighbor found for start tile");
+        enclosed_tiles
     }
 }
 
@@ -178,4 +266,44 @@ impl ToString for TileValue {
             _ => panic!("Invalid tile value"),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_start_shape() {
+        let grid = Grid::parse(".....
+.S-7.
+.|.|.
+.L-J.
+.....");
+        let main_loop = grid.find_main_loop().unwrap();
+        assert_eq!(main_loop.start_value, TileValue::Pipe(Direction::South, Direction::East));
+        assert_eq!(main_loop.positions.len(), 8);
+        assert_eq!(main_loop.enclosed_count(), 1);
+        assert_eq!(main_loop.enclosed_tiles(&grid), vec![(2, 2)]);
+    }
+
+    #[test]
+    fn test_broken_loop() {
+        let grid = Grid::parse(".....
+.S-7.
+.|...
+.L-J.
+.....");
+        assert_eq!(grid.find_main_loop(), Err(LoopError::Broken { position: (3, 3), direction: Direction::North }));
+        assert_eq!(Grid::parse("-|").find_main_loop(), Err(LoopError::NoStart));
+    }
+
+    #[test]
+    fn test_ambiguous_start() {
+        let grid = Grid::parse("F-7F-7
+|.||.|
+L-SJ.|
+..L--J");
+        let error = grid.find_main_loop().unwrap_err();
+        assert!(matches!(error, LoopError::Ambiguous { shapes } if shapes.len() == 2));
+    }
+}
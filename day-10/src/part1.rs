@@ -2,8 +2,8 @@ use crate::common::Grid;
 
 pub fn run(input: &str) -> String {
     let grid = Grid::parse(input);
-    let (main_loop, _) = grid.find_main_loop();
-    let loop_length = main_loop.len();
+    let main_loop = grid.find_main_loop().unwrap();
+    let loop_length = main_loop.positions.len();
     let furthest_tile_distance = loop_length / 2;
     return furthest_tile_distance.to_string();
 }
@@ -49,6 +49,82 @@ pub fn get_shortest_distance(galaxy: &GalaxyPosition, other_galaxy: &GalaxyPosit
 
 pub type GalaxyPosition = (usize, usize);
 
+/// The size of each row and column once the universe has expanded
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpansionWeights {
+    pub rows: Vec<usize>,
+    pub columns: Vec<usize>,
+}
+
+/// The galaxies positions in the expanded universe, computed without expanding the grid
+pub struct GalaxyDistances {
+    positions: Vec<GalaxyPosition>,
+}
+
+impl Universe {
+    /// Weights where each empty row and column is replaced by `factor` of them
+    pub fn expansion_weights(&self, factor: usize) -> ExpansionWeights {
+        let mut rows = vec![1; self.grid.len()];
+        let mut columns = vec![1; self.grid.first().map_or(0, |row| row.len())];
+        for empty_row in self.get_empty_rows() {
+            rows[empty_row] = factor;
+        }
+        for empty_column in self.get_empty_columns() {
+            columns[empty_column] = factor;
+        }
+        ExpansionWeights { rows, columns }
+    }
+
+    pub fn galaxy_distances(&self, weights: &ExpansionWeights) -> GalaxyDistances {
+        let row_offsets = prefix_sums(&weights.rows);
+        let column_offsets = prefix_sums(&weights.columns);
+        let positions = self.get_galaxy_positions().into_iter().map(|(row, column)| (row_offsets[row], column_offsets[column])).collect();
+        GalaxyDistances { positions }
+    }
+}
+
+impl GalaxyDistances {
+    /// The galaxy positions in the expanded universe, in the order of `Universe::get_galaxy_positions`
+    pub fn positions(&self) -> &[GalaxyPosition] {
+        &self.positions
+    }
+
+    /// The distance between two galaxies given by their index
+    pub fn distance(&self, galaxy: usize, other_galaxy: usize) -> usize {
+        get_shortest_distance(&self.positions[galaxy], &self.positions[other_galaxy])
+    }
+
+    /// The sum of the distances between every pair of galaxies, computed on each axis
+    /// by sorting the coordinates: the i-th smallest coordinate is counted i times positively
+    /// and the coordinates before it are counted negatively
+    pub fn total(&self) -> usize {
+        let rows = self.positions.iter().map(|&(row, _)| row).collect();
+        let columns = self.positions.iter().map(|&(_, column)| column).collect();
+        axis_total(rows) + axis_total(columns)
+    }
+}
+
+fn prefix_sums(weights: &[usize]) -> Vec<usize> {
+    let mut sums = Vec::with_capacity(weights.len());
+    let mut total = 0;
+    for weight in weights {
+        sums.push(total);
+        total += weight;
+    }
+    sums
+}
+
+fn axis_total(mut coordinates: Vec<usize>) -> usize {
+    coordinates.sort_unstable();
+    let mut preceding_sum = 0;
+    let mut total = 0;
+    for (index, coordinate) in coordinates.into_iter().enumerate() {
+        total += coordinate * index - preceding_sum;
+        preceding_sum += coordinate;
+    }
+    total
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -60,4 +136,29 @@ mod tests {
         let distance = get_shortest_distance(&galaxy1, &galaxy2);
         assert_eq!(9, distance);
     }
+
+    #[test]
+    fn test_galaxy_distances() {
+        let universe = Universe::parse("#..
+...
+..#
+#..");
+        let weights = universe.expansion_weights(10);
+        assert_eq!(weights, ExpansionWeights { rows: vec![1, 10, 1, 1], columns: vec![1, 10, 1] });
+        let distances = universe.galaxy_distances(&weights);
+        assert_eq!(distances.positions(), &[(0, 0), (11, 11), (12, 0)]);
+        assert_eq!(distances.distance(0, 1), 22);
+        assert_eq!(distances.total(), 22 + 12 + 12);
+    }
+
+    #[test]
+    fn test_custom_weights() {
+        let universe = Universe::parse("#.#
+...
+#.#");
+        let weights = ExpansionWeights { rows: vec![1, 3, 1], columns: vec![2, 1, 1] };
+        let distances = universe.galaxy_distances(&weights);
+        assert_eq!(distances.positions(), &[(0, 0), (0, 3), (4, 0), (4, 3)]);
+        assert_eq!(distances.total(), 4 * 4 + 4 * 3);
+    }
 }
\ No newline at end of file
@@ -1,40 +1,12 @@
-use std::vec;
-
-use crate::common::{self, Universe, UniversePoint};
+use crate::common::{Universe, UniversePoint};
 
 pub fn run(input: &str) -> String {
     let universe = Universe::parse(input);
-    let expanded_universe = universe.expand();
-    let galaxy_positions = expanded_universe.get_galaxy_positions();
-    let mut galaxy_distance_total = 0;
-    for (galaxy_index, galaxy) in galaxy_positions.iter().enumerate() {
-        for other_galaxy in galaxy_positions.iter().skip(galaxy_index + 1) {
-            let distance = common::get_shortest_distance(galaxy, other_galaxy);
-            galaxy_distance_total += distance;
-        }
-    }
+    let weights = universe.expansion_weights(2);
+    let galaxy_distance_total = universe.galaxy_distances(&weights).total();
     galaxy_distance_total.to_string()
 }
 
-impl Universe {
-
-    fn expand(self) -> Self {
-        let empty_rows = self.get_empty_rows();
-        let empty_columns = self.get_empty_columns();
-        let mut grid = self.grid;
-        for (empty_columns_already_inserted, empty_column) in empty_columns.into_iter().enumerate() {
-            for row in &mut grid {
-                row.insert(empty_column + empty_columns_already_inserted , UniversePoint::Empty);
-            }
-        }
-        let row_length = grid[0].len();
-        for (empty_rows_already_inserted, empty_row) in empty_rows.into_iter().enumerate() {
-            grid.insert(empty_row + empty_rows_already_inserted, vec![UniversePoint::Empty; row_length]);
-        }
-        Self { grid }
-    }
-}
-
 impl ToString for Universe {
     fn to_string(&self) -> String {
         self.grid.iter().map(|row| {
@@ -69,7 +41,7 @@ mod tests {
     }
 
     #[test]
-    fn test_expand_function() {
+    fn test_expanded_galaxy_positions() {
         let input = "...#......
 .......#..
 #.........
@@ -80,22 +52,11 @@ mod tests {
 ..........
 .......#..
 #...#.....";
-        let expected_output = "....#........
-.........#...
-#............
-.............
-.............
-........#....
-.#...........
-............#
-.............
-.............
-.........#...
-#....#.......";
         let universe = Universe::parse(input);
-        let expanded_universe = universe.expand();
-        let actual_output = expanded_universe.to_string();
-        assert_eq!(expected_output, actual_output);
+        let distances = universe.galaxy_distances(&universe.expansion_weights(2));
+        let expected_positions = [(0, 4), (1, 9), (2, 0), (5, 8), (6, 1), (7, 12), (10, 9), (11, 0), (11, 5)];
+        assert_eq!(distances.positions(), &expected_positions);
+        assert_eq!(distances.distance(4, 8), 9);
     }
 
     // Add more tests here
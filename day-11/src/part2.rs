@@ -1,4 +1,4 @@
-use crate::common::{self, GalaxyPosition, Universe};
+use crate::common::Universe;
 
 pub fn run(input: &str) -> String {
     run_with_factor(input, 1_000_000)
@@ -6,31 +6,11 @@ pub fn run(input: &str) -> String {
 
 fn run_with_factor(input: &str, factor: usize) -> String {
     let universe = Universe::parse(input);
-    let galaxy_positions = universe.expand_large(factor);
-    let mut galaxy_distance_total = 0;
-    for (galaxy_index, galaxy) in galaxy_positions.iter().enumerate() {
-        for other_galaxy in galaxy_positions.iter().skip(galaxy_index + 1) {
-            let distance = common::get_shortest_distance(galaxy, other_galaxy);
-            galaxy_distance_total += distance;
-        }
-    }
+    let weights = universe.expansion_weights(factor);
+    let galaxy_distance_total = universe.galaxy_distances(&weights).total();
     galaxy_distance_total.to_string()
 }
 
-impl Universe {
-    fn expand_large(&self, factor: usize) -> Vec<GalaxyPosition> {
-        let empty_rows = self.get_empty_rows();
-        let empty_columns = self.get_empty_columns();
-        let galaxy_positions = self.get_galaxy_positions();
-        let expanded_galaxy_positions = galaxy_positions.iter().map(|(row, column)| {
-            let empty_rows_before = empty_rows.iter().filter(|&empty_row| empty_row < row).count();
-            let empty_columns_before = empty_columns.iter().filter(|&empty_column| empty_column < column).count();
-            (row + empty_rows_before * (factor - 1), column + empty_columns_before * (factor- 1))
-        }).collect();
-        expanded_galaxy_positions
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
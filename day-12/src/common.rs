@@ -46,6 +46,146 @@ pub fn broken_groups(states: &[SpringState]) -> Vec<u32> {
     groups
 }
 
+impl SpringState {
+
+    pub fn to_char(&self) -> char {
+        match self {
+            Working => '.',
+            Broken => '#',
+        }
+    }
+    
+}
+
+impl SpringsRow {
+    /// Repeats the row `factor` times, with an unknown spring between the copies
+    pub fn unfold(&self, factor: usize) -> Self {
+        let mut states = Vec::with_capacity((self.states.len() + 1) * factor);
+        let mut broken_groups = Vec::with_capacity(self.broken_groups.len() * factor);
+        for copy in 0..factor {
+            if copy > 0 {
+                states.push(None);
+            }
+            states.extend_from_slice(&self.states);
+            broken_groups.extend_from_slice(&self.broken_groups);
+        }
+        Self { states, broken_groups }
+    }
+
+    pub fn arrangements(&self) -> Arrangements<'_> {
+        Arrangements::new(&self.states, &self.broken_groups)
+    }
+}
+
+/// The valid arrangements of a row of springs, counted with a dynamic program over
+/// (position, group): `ways[position][group]` is the number of ways to place the groups
+/// from `group` onwards in the states from `position` onwards, `position` being at the start
+/// of a run. Placing a whole group at once removes the need to track the run length.
+pub struct Arrangements<'a> {
+    states: &'a [Option<SpringState>],
+    groups: &'a [u32],
+    ways: Vec<u128>,
+}
+
+impl<'a> Arrangements<'a> {
+    pub fn new(states: &'a [Option<SpringState>], groups: &'a [u32]) -> Self {
+        let mut arrangements = Self { states, groups, ways: vec![0; (states.len() + 1) * (groups.len() + 1)] };
+        let index = arrangements.index(states.len(), groups.len());
+        arrangements.ways[index] = 1;
+        for position in (0..states.len()).rev() {
+            for group in 0..=groups.len() {
+                let ways = arrangements.ways_with(position, group, Working) + arrangements.ways_with(position, group, Broken);
+                let index = arrangements.index(position, group);
+                arrangements.ways[index] = ways;
+            }
+        }
+        arrangements
+    }
+
+    fn index(&self, position: usize, group: usize) -> usize {
+        position * (self.groups.len() + 1) + group
+    }
+
+    fn ways(&self, position: usize, group: usize) -> u128 {
+        self.ways[self.index(position, group)]
+    }
+
+    /// Where the next run starts after putting `state` at `position`, if the state fits
+    fn next_position(&self, position: usize, group: usize, state: SpringState) -> Option<(usize, usize)> {
+        match state {
+            Working => (self.states[position] != Some(Broken)).then_some((position + 1, group)),
+            Broken => {
+                let size = *self.groups.get(group)? as usize;
+                let end = position + size;
+                let fits = end <= self.states.len()
+                    && !self.states[position..end].contains(&Some(Working))
+                    && self.states.get(end) != Some(&Some(Broken));
+                fits.then_some(((end + 1).min(self.states.len()), group + 1))
+            }
+        }
+    }
+
+    fn ways_with(&self, position: usize, group: usize, state: SpringState) -> u128 {
+        self.next_position(position, group, state).map_or(0, |(position, group)| self.ways(position, group))
+    }
+
+    pub fn count(&self) -> u128 {
+        self.ways(0, 0)
+    }
+
+    /// The k-th arrangement (starting from 0) in lexicographic order, `#` coming before `.`
+    pub fn nth(&self, mut k: u128) -> Option<Vec<SpringState>> {
+        if k >= self.count() {
+            return None;
+        }
+        let mut arrangement = Vec::with_capacity(self.states.len());
+        let (mut position, mut group) = (0, 0);
+        while position < self.states.len() {
+            let broken_ways = self.ways_with(position, group, Broken);
+            let (next_position, next_group) = if k < broken_ways {
+                self.next_position(position, group, Broken).unwrap()
+            } else {
+                k -= broken_ways;
+                self.next_position(position, group, Working).unwrap()
+            };
+            if next_group > group {
+                let end = position + self.groups[group] as usize;
+                arrangement.extend(std::iter::repeat_n(Broken, end - position));
+                arrangement.extend(std::iter::repeat_n(Working, next_position - end));
+            } else {
+                arrangement.push(Working);
+            }
+            (position, group) = (next_position, next_group);
+        }
+        Some(arrangement)
+    }
+
+    /// Lazily yields every arrangement in lexicographic order
+    pub fn iter(&self) -> ArrangementsIter<'_, 'a> {
+        ArrangementsIter { arrangements: self, next: 0 }
+    }
+}
+
+pub struct ArrangementsIter<'b, 'a> {
+    arrangements: &'b Arrangements<'a>,
+    next: u128,
+}
+
+impl Iterator for ArrangementsIter<'_, '_> {
+    type Item = Vec<SpringState>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let arrangement = self.arrangements.nth(self.next)?;
+        self.next += 1;
+        Some(arrangement)
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.next = self.next.saturating_add(n as u128);
+        self.next()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -59,4 +199,31 @@ mod tests {
         let output = broken_groups(&input);
         assert_eq!(output, expected_output)
     }
+
+    fn render(states: &[SpringState]) -> String {
+        states.iter().map(|state| state.to_char()).collect()
+    }
+
+    #[test]
+    fn test_arrangements_in_order() {
+        let row = SpringsRow::parse(".??..??...?##. 1,1,3");
+        let arrangements = row.arrangements();
+        assert_eq!(arrangements.count(), 4);
+        let rendered: Vec<String> = arrangements.iter().map(|states| render(&states)).collect();
+        assert_eq!(rendered, vec![".#...#....###.", ".#....#...###.", "..#..#....###.", "..#...#...###."]);
+        assert!(arrangements.iter().all(|states| broken_groups(&states) == row.broken_groups));
+        assert_eq!(arrangements.nth(2).map(|states| render(&states)), Some("..#..#....###.".to_string()));
+        assert_eq!(arrangements.iter().nth(3).map(|states| render(&states)), Some("..#...#...###.".to_string()));
+        assert_eq!(arrangements.nth(4), None);
+    }
+
+    #[test]
+    fn test_unfold() {
+        let row = SpringsRow::parse("???.### 1,1,3");
+        assert_eq!(row.unfold(2), SpringsRow::parse("???.###????.### 1,1,3,1,1,3"));
+        assert_eq!(row.unfold(5).arrangements().count(), 1);
+        let row = SpringsRow::parse(".??..??...?##. 1,1,3");
+        assert_eq!(row.unfold(5).arrangements().count(), 16384);
+        assert_eq!(row.unfold(1), row);
+    }
 }
\ No newline at end of file
@@ -1,4 +1,5 @@
 pub mod part1;
 pub mod part2;
+pub mod nonogram;
 
-mod common;
\ No newline at end of file
+pub mod common;
\ No newline at end of file
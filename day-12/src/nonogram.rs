@@ -0,0 +1,199 @@
+use std::collections::VecDeque;
+
+use crate::common::{Arrangements, SpringState};
+use SpringState::{Broken, Working};
+
+/// A grid of cells, None for the cells that are still unknown
+pub type NonogramGrid = Vec<Vec<Option<SpringState>>>;
+
+/// A nonogram, where each row and column is constrained like a `SpringsRow`
+#[derive(Debug, PartialEq, Eq)]
+pub struct Nonogram {
+    pub row_groups: Vec<Vec<u32>>,
+    pub column_groups: Vec<Vec<u32>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Line {
+    Row(usize),
+    Column(usize),
+}
+
+impl Nonogram {
+    /// Parses the groups of the rows, then an empty line, then the groups of the columns.
+    /// Each line lists its groups separated with commas, `0` stands for a line without groups.
+    pub fn parse(input: &str) -> Self {
+        let mut blocks = input.split("\n\n");
+        let mut parse_block = || -> Vec<Vec<u32>> {
+            blocks
+                .next()
+                .unwrap()
+                .lines()
+                .map(|line| line.split(",").map(|size| size.trim().parse().unwrap()).filter(|&size| size > 0).collect())
+                .collect()
+        };
+        let row_groups = parse_block();
+        let column_groups = parse_block();
+        Self { row_groups, column_groups }
+    }
+
+    pub fn empty_grid(&self) -> NonogramGrid {
+        vec![vec![None; self.column_groups.len()]; self.row_groups.len()]
+    }
+
+    /// Fixes every cell that has the same state in all the valid arrangements of its row or column,
+    /// until no more cell can be fixed. Returns None if a line has no valid arrangement.
+    pub fn propagate(&self, mut grid: NonogramGrid) -> Option<NonogramGrid> {
+        let mut queue: VecDeque<Line> = (0..self.row_groups.len()).map(Line::Row).chain((0..self.column_groups.len()).map(Line::Column)).collect();
+        while let Some(line) = queue.pop_front() {
+            let (states, groups) = match line {
+                Line::Row(row) => (grid[row].clone(), &self.row_groups[row]),
+                Line::Column(column) => (grid.iter().map(|row| row[column]).collect(), &self.column_groups[column]),
+            };
+            let solved = solve_line(&states, groups)?;
+            for (index, (before, after)) in states.iter().zip(solved).enumerate() {
+                if *before == after {
+                    continue;
+                }
+                let crossing = match line {
+                    Line::Row(row) => {
+                        grid[row][index] = after;
+                        Line::Column(index)
+                    }
+                    Line::Column(column) => {
+                        grid[index][column] = after;
+                        Line::Row(index)
+                    }
+                };
+                if !queue.contains(&crossing) {
+                    queue.push_back(crossing);
+                }
+            }
+        }
+        Some(grid)
+    }
+
+    /// Finds up to `limit` solutions, propagating constraints and guessing a cell when stuck
+    pub fn solutions(&self, limit: usize) -> Vec<Vec<Vec<SpringState>>> {
+        let mut solutions = Vec::new();
+        self.backtrack(self.empty_grid(), limit, &mut solutions);
+        solutions
+    }
+
+    pub fn solve(&self) -> Option<Vec<Vec<SpringState>>> {
+        self.solutions(1).pop()
+    }
+
+    pub fn is_unique(&self) -> bool {
+        self.solutions(2).len() == 1
+    }
+
+    fn backtrack(&self, grid: NonogramGrid, limit: usize, solutions: &mut Vec<Vec<Vec<SpringState>>>) {
+        if solutions.len() >= limit {
+            return;
+        }
+        let Some(grid) = self.propagate(grid) else {
+            return;
+        };
+        let unknown = grid.iter().enumerate().find_map(|(row, states)| states.iter().position(|state| state.is_none()).map(|column| (row, column)));
+        match unknown {
+            None => solutions.push(grid.into_iter().map(|row| row.into_iter().map(Option::unwrap).collect()).collect()),
+            Some((row, column)) => {
+                for guess in [Broken, Working] {
+                    let mut guessed = grid.clone();
+                    guessed[row][column] = Some(guess);
+                    self.backtrack(guessed, limit, solutions);
+                }
+            }
+        }
+    }
+}
+
+/// Intersects all the valid arrangements of a line, by checking for each unknown cell
+/// whether arrangements remain once it is fixed to each state
+fn solve_line(states: &[Option<SpringState>], groups: &[u32]) -> Option<Vec<Option<SpringState>>> {
+    if Arrangements::new(states, groups).count() == 0 {
+        return None;
+    }
+    let mut solved = states.to_vec();
+    for index in 0..states.len() {
+        if states[index].is_some() {
+            continue;
+        }
+        let mut possible = [Broken, Working].into_iter().filter(|&state| {
+            let mut candidate = states.to_vec();
+            candidate[index] = Some(state);
+            Arrangements::new(&candidate, groups).count() > 0
+        });
+        if let (Some(state), None) = (possible.next(), possible.next()) {
+            solved[index] = Some(state);
+        }
+    }
+    Some(solved)
+}
+
+/// Renders a grid as text, `?` marking the unknown cells
+pub fn render(grid: &NonogramGrid) -> String {
+    grid.iter().map(|row| row.iter().map(|state| state.map_or('?', |state| state.to_char())).collect::<String>()).collect::<Vec<_>>().join("\n")
+}
+
+/// Renders a solution as a plain PBM image, broken springs being the black pixels
+pub fn render_pbm(solution: &[Vec<SpringState>]) -> String {
+    let width = solution.first().map_or(0, |row| row.len());
+    let mut image = format!("P1\n{} {}\n", width, solution.len());
+    for row in solution {
+        let pixels: Vec<&str> = row.iter().map(|&state| if state == Broken { "1" } else { "0" }).collect();
+        image.push_str(&pixels.join(" "));
+        image.push('\n');
+    }
+    image
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEART: &str = "1,1
+5
+5
+3
+1
+
+2
+4
+4
+4
+2";
+
+    #[test]
+    fn test_solve_line() {
+        let states = vec![None; 5];
+        let solved = solve_line(&states, &[4]).unwrap();
+        assert_eq!(solved, vec![None, Some(Broken), Some(Broken), Some(Broken), None]);
+        assert_eq!(solve_line(&[Some(Working), None], &[2]), None);
+    }
+
+    #[test]
+    fn test_solve() {
+        let nonogram = Nonogram::parse(HEART);
+        let solution = nonogram.solve().unwrap();
+        let grid = solution.iter().map(|row| row.iter().map(|&state| Some(state)).collect()).collect();
+        assert_eq!(render(&grid), ".#.#.
+#####
+#####
+.###.
+..#..");
+        assert!(nonogram.is_unique());
+        assert!(render_pbm(&solution).starts_with("P1\n5 5\n0 1 0 1 0\n"));
+    }
+
+    #[test]
+    fn test_backtracking() {
+        let nonogram = Nonogram::parse("1\n1\n\n1\n1");
+        let grid = nonogram.propagate(nonogram.empty_grid()).unwrap();
+        assert_eq!(render(&grid), "??\n??");
+        assert_eq!(nonogram.solutions(10).len(), 2);
+        assert!(!nonogram.is_unique());
+        assert_eq!(Nonogram::parse("2\n0\n\n0\n1").solve(), None);
+    }
+}
@@ -1,6 +1,4 @@
-use SpringState::{Working, Broken};
-
-use crate::common::{self, SpringState, SpringsRow};
+use crate::common::SpringsRow;
 
 pub fn run(input: &str) -> String {
     let rows = input.lines().map(SpringsRow::parse);
@@ -10,62 +8,11 @@ pub fn run(input: &str) -> String {
 }
 
 impl SpringsRow {
-
-    fn all_possible_states(&self) -> StatesIterator {
-        StatesIterator::new(&self)
-    }
-
     fn count_valid_states(&self) -> usize {
-        self.all_possible_states().filter(|states| common::broken_groups(states) == self.broken_groups).count()
-    }
-}
-
-struct StatesIterator<'a> {
-    row: &'a SpringsRow,
-    count: u32,
-    number_of_unknows: u32
-}
-
-impl<'a> StatesIterator<'a> {
-    fn new(row: &'a SpringsRow) -> Self {
-        let number_of_unknows = row.states.iter().filter(|state| state.is_none() ).count() as u32;
-        Self { row, count: 0, number_of_unknows }
+        self.arrangements().count() as usize
     }
 }
 
-impl Iterator for StatesIterator<'_> {
-    type Item = Vec<SpringState>;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.count == (1 << self.number_of_unknows) {
-            // The bitmap reached the end;
-            return None
-        }
-        let mut states = Vec::with_capacity(self.row.states.len());
-        let mut unknown_index = 0usize;
-        for &state in &self.row.states {
-            if let Some(value) = state {
-                states.push(value);
-            } else {
-                // count acts as a bitmap for unknown states
-                let value = if (self.count >> unknown_index & 1) == 1 {
-                    Working
-                } else { 
-                    Broken 
-                };
-                unknown_index += 1;
-                states.push(value);
-            };
-        }
-        self.count += 1; // Update the bitmap for the next state;
-        Some(states)
-    }
-    
-}
-
-
-
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,43 +1,15 @@
-use crate::common::{Line, Pattern, SoilType};
+use crate::common::Pattern;
 
 pub fn run(input: &str) -> String {
     let patterns = input.split("\n\n");
     let patterns = patterns.map(Pattern::parse);
     let mut sum = 0;
     for pattern in patterns {
-        sum += pattern.value(find_symmetry)
+        sum += pattern.summary(0).expect("No reflection")
     }
     sum.to_string()
 }
 
-fn encode_as_int(values: &Line) -> u32 {
-    values.into_iter().enumerate().map(|(index, value)| {
-        match value {
-            SoilType::Ash => 0,
-            SoilType::Rock => 1 << index,
-        }
-    }).sum()
-}
-
-fn find_symmetry(values: &[Line]) -> Option<usize> {
-    let values: Vec<u32> = values.iter().map(|line| encode_as_int(line)).collect();
-    let values_reversed : Vec<u32> = Vec::from_iter(values.iter().rev().map(|&val|val));
-    for separation in 1..values.len() {
-        let left_reversed = &values_reversed[values.len() - separation..];
-        let right = &values[separation..];
-        let (longest, shortest) = if left_reversed.len() > right.len() {
-            (left_reversed, right)
-        } else {
-            (right, left_reversed)
-        };
-        
-        if longest.starts_with(shortest) {
-            return Some(separation);
-        }
-    }
-    None
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -64,7 +36,7 @@ mod tests {
     }
 
     #[test]
-    fn test_find_symmetry() {
+    fn test_summary() {
         let input = "#.##..##.
 ..#.##.#.
 ##......#
@@ -73,8 +45,7 @@ mod tests {
 ..##..##.
 #.#.##.#.";
         let pattern = Pattern::parse(&input);
-        let output = find_symmetry(&pattern.columns());
-        assert_eq!(Some(5), output)
+        assert_eq!(Some(5), pattern.summary(0))
     }
 
     // Add more tests here
@@ -0,0 +1,231 @@
+use crate::common::{Pattern, SoilType};
+
+/// A cell of a pattern, as (row, column)
+pub type Position = (usize, usize);
+
+/// A line across which a pattern is mirrored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    /// Between two rows, with the given number of rows above it
+    Horizontal(usize),
+    /// Between two columns, with the given number of columns on its left
+    Vertical(usize),
+    /// The diagonal going from the top left corner to the bottom right one
+    Diagonal,
+    /// The diagonal going from the top right corner to the bottom left one
+    AntiDiagonal,
+}
+
+/// A pair of mirrored cells that don't have the same soil type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Smudge {
+    /// The cell above or on the left of the axis
+    pub cell: Position,
+    pub mirror: Position,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Reflection {
+    pub axis: Axis,
+    pub smudges: Vec<Smudge>,
+}
+
+/// A line of cells packed in 64 bits words, a set bit being a rock
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Bits {
+    words: Vec<u64>,
+}
+
+impl Bits {
+    fn new<I: IntoIterator<Item = SoilType>>(soil: I) -> Bits {
+        let mut words = Vec::new();
+        for (index, soil_type) in soil.into_iter().enumerate() {
+            if index % 64 == 0 {
+                words.push(0);
+            }
+            if soil_type == SoilType::Rock {
+                words[index / 64] |= 1 << (index % 64);
+            }
+        }
+        Bits { words }
+    }
+
+    fn xor(&self, other: &Bits) -> Bits {
+        Bits { words: self.words.iter().zip(&other.words).map(|(a, b)| a ^ b).collect() }
+    }
+
+    fn count_ones(&self) -> usize {
+        self.words.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// The indexes of the set bits, in increasing order
+    fn ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(word_index, &word)| {
+            (0..64).filter(move |bit| word >> bit & 1 == 1).map(move |bit| word_index * 64 + bit)
+        })
+    }
+}
+
+impl Axis {
+    /// The value of the axis in the puzzle summary, diagonals don't have one
+    pub fn summary(&self) -> Option<usize> {
+        match *self {
+            Axis::Horizontal(rows_above) => Some(100 * rows_above),
+            Axis::Vertical(columns_left) => Some(columns_left),
+            Axis::Diagonal | Axis::AntiDiagonal => None,
+        }
+    }
+}
+
+impl Pattern {
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn width(&self) -> usize {
+        self.rows.first().map_or(0, |row| row.len())
+    }
+
+    /// Finds every axis across which exactly `smudges` pairs of mirrored cells differ.
+    /// Horizontal and vertical axes only compare the lines that have a mirror inside the pattern,
+    /// diagonal axes are only considered for square patterns.
+    pub fn reflections(&self, smudges: usize) -> Vec<Reflection> {
+        let rows: Vec<Bits> = self.rows.iter().map(|row| Bits::new(row.iter().copied())).collect();
+        let columns: Vec<Bits> = (0..self.width()).map(|column| Bits::new(self.rows.iter().map(|row| row[column]))).collect();
+        let mut reflections = Vec::new();
+        for rows_above in 1..self.height() {
+            let found = mirror_lines(&rows, rows_above, smudges, |line, mirror, column| ((line, column), (mirror, column)));
+            reflections.extend(found.map(|smudges| Reflection { axis: Axis::Horizontal(rows_above), smudges }));
+        }
+        for columns_left in 1..self.width() {
+            let found = mirror_lines(&columns, columns_left, smudges, |line, mirror, row| ((row, line), (row, mirror)));
+            reflections.extend(found.map(|smudges| Reflection { axis: Axis::Vertical(columns_left), smudges }));
+        }
+        if self.height() == self.width() {
+            let size = self.height();
+            // Row r of the cell (r, c) is compared with column r, which holds its mirror (c, r)
+            let diagonal = mirror_diagonal(&rows, &columns, smudges, |row, column| (column > row).then_some((column, row)));
+            reflections.extend(diagonal.map(|smudges| Reflection { axis: Axis::Diagonal, smudges }));
+            // The mirror of (r, c) is (size - 1 - c, size - 1 - r), so row r is compared with the reversed column size - 1 - r
+            let reversed_columns: Vec<Bits> = (0..size)
+                .map(|row| Bits::new(self.rows.iter().rev().map(|cells| cells[size - 1 - row])))
+                .collect();
+            let anti_diagonal = mirror_diagonal(&rows, &reversed_columns, smudges, |row, column| {
+                (row + column < size - 1).then_some((size - 1 - column, size - 1 - row))
+            });
+            reflections.extend(anti_diagonal.map(|smudges| Reflection { axis: Axis::AntiDiagonal, smudges }));
+        }
+        reflections
+    }
+
+    /// The puzzle summary of the first horizontal or vertical reflection with exactly `smudges` smudges
+    pub fn summary(&self, smudges: usize) -> Option<usize> {
+        self.reflections(smudges).iter().find_map(|reflection| reflection.axis.summary())
+    }
+}
+
+/// Compares the lines mirrored across the axis placed after `split` lines,
+/// `to_cells` turns a line, its mirror and the index of a differing bit into a smudge
+fn mirror_lines<F>(lines: &[Bits], split: usize, smudges: usize, to_cells: F) -> Option<Vec<Smudge>>
+where
+    F: Fn(usize, usize, usize) -> (Position, Position),
+{
+    let mut found = Vec::new();
+    for offset in 0..split.min(lines.len() - split) {
+        let (line, mirror) = (split - 1 - offset, split + offset);
+        let differences = lines[line].xor(&lines[mirror]);
+        if found.len() + differences.count_ones() > smudges {
+            return None;
+        }
+        found.extend(differences.ones().map(|bit| {
+            let (cell, mirror) = to_cells(line, mirror, bit);
+            Smudge { cell, mirror }
+        }));
+    }
+    (found.len() == smudges).then_some(found)
+}
+
+/// Compares each row with its counterpart across a diagonal,
+/// `mirror_of` returns the mirror of a cell if it is on the upper side of the diagonal
+fn mirror_diagonal<F>(rows: &[Bits], counterparts: &[Bits], smudges: usize, mirror_of: F) -> Option<Vec<Smudge>>
+where
+    F: Fn(usize, usize) -> Option<Position>,
+{
+    let mut found = Vec::new();
+    for (row, (cells, mirrors)) in rows.iter().zip(counterparts).enumerate() {
+        for column in cells.xor(mirrors).ones() {
+            if let Some(mirror) = mirror_of(row, column) {
+                found.push(Smudge { cell: (row, column), mirror });
+                if found.len() > smudges {
+                    return None;
+                }
+            }
+        }
+    }
+    (found.len() == smudges).then_some(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIRST: &str = "#.##..##.
+..#.##.#.
+##......#
+##......#
+..#.##.#.
+..##..##.
+#.#.##.#.";
+
+    const SECOND: &str = "#...##..#
+#....#..#
+..##..###
+#####.##.
+#####.##.
+..##..###
+#....#..#";
+
+    fn axes(pattern: &Pattern, smudges: usize) -> Vec<Axis> {
+        pattern.reflections(smudges).into_iter().map(|reflection| reflection.axis).collect()
+    }
+
+    #[test]
+    fn test_exact_reflections() {
+        assert_eq!(axes(&Pattern::parse(FIRST), 0), vec![Axis::Vertical(5)]);
+        assert_eq!(axes(&Pattern::parse(SECOND), 0), vec![Axis::Horizontal(4)]);
+        assert_eq!(Pattern::parse(FIRST).summary(0), Some(5));
+    }
+
+    #[test]
+    fn test_smudges() {
+        let reflections = Pattern::parse(FIRST).reflections(1);
+        assert_eq!(reflections[0], Reflection { axis: Axis::Horizontal(3), smudges: vec![Smudge { cell: (0, 0), mirror: (5, 0) }] });
+        let reflections = Pattern::parse(SECOND).reflections(1);
+        assert_eq!(reflections[0], Reflection { axis: Axis::Horizontal(1), smudges: vec![Smudge { cell: (0, 4), mirror: (1, 4) }] });
+        assert_eq!(Pattern::parse(SECOND).summary(1), Some(100));
+        assert!(Pattern::parse(FIRST).reflections(2).iter().all(|reflection| reflection.smudges.len() == 2));
+    }
+
+    #[test]
+    fn test_diagonals() {
+        let pattern = Pattern::parse("#.#\n.#.\n#..");
+        assert_eq!(axes(&pattern, 0), vec![Axis::Diagonal]);
+        assert_eq!(pattern.reflections(1), vec![Reflection { axis: Axis::AntiDiagonal, smudges: vec![Smudge { cell: (0, 0), mirror: (2, 2) }] }]);
+        let pattern = Pattern::parse("..#\n.#.\n#..");
+        assert_eq!(axes(&pattern, 0), vec![Axis::Diagonal, Axis::AntiDiagonal]);
+        assert_eq!(axes(&Pattern::parse("#..#\n.##."), 0), vec![Axis::Vertical(2)]);
+        assert_eq!(Axis::Diagonal.summary(), None);
+    }
+
+    #[test]
+    fn test_wide_pattern() {
+        let half = "#.###...#.##.#...####..#.#.##...#.#####.";
+        let row: String = half.chars().chain(half.chars().rev()).collect();
+        let other: String = row.chars().map(|c| if c == '#' { '.' } else { '#' }).collect();
+        let pattern = Pattern::parse(&format!("{}\n{}\n{}", row, other, other));
+        assert_eq!(axes(&pattern, 0), vec![Axis::Horizontal(2), Axis::Vertical(half.len())]);
+        let smudged = format!("{}\n{}", row, row.replacen('#', ".", 1));
+        let reflections = Pattern::parse(&smudged).reflections(1);
+        assert_eq!(reflections[0], Reflection { axis: Axis::Horizontal(1), smudges: vec![Smudge { cell: (0, 0), mirror: (1, 0) }] });
+    }
+}
@@ -1,3 +1,7 @@
+use std::{fmt, ops::Range};
+
+use crate::program::TiltProgram;
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Tile {
     Empty,
@@ -5,10 +9,23 @@ pub enum Tile {
     CubeRock,
 }
 
+/// A platform stored as bitboards, each row being packed in 64 bits words.
+/// Cube rocks never move, so the free segments between them are computed once when parsing.
+#[derive(Debug, Clone)]
 pub struct Platform {
-    rows: Vec<Vec<Tile>>
+    width: usize,
+    height: usize,
+    words_per_row: usize,
+    round: Vec<u64>,
+    cube: Vec<u64>,
+    /// For each row, the ranges of columns between two cube rocks
+    row_segments: Vec<Vec<Range<usize>>>,
 }
 
+/// The positions of the round rocks, which is all that changes when tilting a platform
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PlatformState(Box<[u64]>);
+
 impl Tile {
     pub fn parse(char: &char) -> Self {
         match char {
@@ -22,43 +39,161 @@ impl Tile {
 
 impl Platform {
     pub fn parse(input: &str) -> Self {
-        let rows = input.lines().map(|line| line.chars().map(|char| Tile::parse(&char)).collect()).collect();
-        Self { rows }
+        let rows: Vec<Vec<Tile>> = input.lines().map(|line| line.chars().map(|char| Tile::parse(&char)).collect()).collect();
+        let height = rows.len();
+        let width = rows.first().map_or(0, |row| row.len());
+        let words_per_row = width.div_ceil(64);
+        let mut round = vec![0; height * words_per_row];
+        let mut cube = vec![0; height * words_per_row];
+        for (row_index, row) in rows.iter().enumerate() {
+            for (column, tile) in row.iter().enumerate() {
+                let bits = match tile {
+                    Tile::RoundRock => &mut round,
+                    Tile::CubeRock => &mut cube,
+                    Tile::Empty => continue,
+                };
+                bits[row_index * words_per_row + column / 64] |= 1 << (column % 64);
+            }
+        }
+        let row_segments = rows
+            .iter()
+            .map(|row| {
+                row.split(|tile| *tile == Tile::CubeRock)
+                    .scan(0, |start, segment| {
+                        let range = *start..*start + segment.len();
+                        *start = range.end + 1;
+                        Some(range)
+                    })
+                    .filter(|range| !range.is_empty())
+                    .collect()
+            })
+            .collect();
+        Self { width, height, words_per_row, round, cube, row_segments }
+    }
+
+    pub fn tile(&self, row: usize, column: usize) -> Tile {
+        let index = row * self.words_per_row + column / 64;
+        let bit = 1 << (column % 64);
+        if self.round[index] & bit != 0 {
+            Tile::RoundRock
+        } else if self.cube[index] & bit != 0 {
+            Tile::CubeRock
+        } else {
+            Tile::Empty
+        }
+    }
+
+    pub fn state(&self) -> PlatformState {
+        PlatformState(self.round.clone().into_boxed_slice())
     }
 }
 
-#[derive(PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Direction {
     North, South, West, East
 }
 
 impl Platform {
+    pub fn tilt(mut self, direction: Direction) -> Self {
+        self.tilt_in_place(direction);
+        self
+    }
 
-    fn columns(&self) -> Vec<Vec<Tile>> {
-        (0..self.rows[0].len()).map(|column| {
-            self.rows.iter().map(|row| row[column]).collect()
-        }).collect()
+    pub fn tilt_in_place(&mut self, direction: Direction) {
+        match direction {
+            Direction::West | Direction::East => self.tilt_rows(direction == Direction::West),
+            Direction::North => self.tilt_columns(&(0..self.height).collect::<Vec<_>>()),
+            Direction::South => self.tilt_columns(&(0..self.height).rev().collect::<Vec<_>>()),
+        }
     }
 
-    fn from_columns(columns: &Vec<Vec<Tile>>) -> Self {
-        let rows = (0..columns[0].len()).map(|row| {
-            columns.iter().map(|column| column[row]).collect()
-        }).collect();
-        Self { rows }
+    /// Rolls the rocks of each free segment of the rows to one of its ends.
+    /// Each segment only needs its rocks to be counted and its bits to be rewritten.
+    fn tilt_rows(&mut self, to_start: bool) {
+        for row in 0..self.height {
+            let bits = &mut self.round[row * self.words_per_row..(row + 1) * self.words_per_row];
+            for segment in &self.row_segments[row] {
+                let count = count_ones(bits, segment.clone());
+                set_range(bits, segment.clone(), false);
+                let rocks = if to_start { segment.start..segment.start + count } else { segment.end - count..segment.end };
+                set_range(bits, rocks, true);
+            }
+        }
     }
 
-    pub fn tilt(self, direction: Direction) -> Self {
-        let lines = if direction == Direction::West || direction == Direction::East {
-            self.rows
-        } else {
-            self.columns()
-        };
-        let to_start = direction == Direction::West || direction == Direction::North;
-        let new_lines = lines.iter().map(|line| tilt(line, to_start)).collect();
-        if direction == Direction::West || direction == Direction::East {
-            Self { rows: new_lines }
+    /// Rolls the rocks along the columns, towards the first row of `order`.
+    /// The 64 columns of a word are handled at once with bit-sliced counters, i.e. bit `b` of
+    /// the counters of all the columns is stored in a single word. A first pass counts, for each row,
+    /// the rocks between it and the next cube rock, a second pass stacks that many rocks after each cube rock.
+    fn tilt_columns(&mut self, order: &[usize]) {
+        let counter_bits = (usize::BITS - self.height.leading_zeros()) as usize;
+        let mut rocks_until_cube = vec![0u64; self.height * counter_bits];
+        for word in 0..self.words_per_row {
+            let mut counter = vec![0u64; counter_bits];
+            for &row in order.iter().rev() {
+                let index = row * self.words_per_row + word;
+                increment(&mut counter, self.round[index]);
+                counter.iter_mut().for_each(|bits| *bits &= !self.cube[index]);
+                rocks_until_cube[row * counter_bits..(row + 1) * counter_bits].copy_from_slice(&counter);
+            }
+            let mut remaining = vec![0u64; counter_bits];
+            let mut previous_cube = !0;
+            for &row in order {
+                let index = row * self.words_per_row + word;
+                let segment_start = previous_cube & !self.cube[index];
+                for (bit, &count) in remaining.iter_mut().zip(&rocks_until_cube[row * counter_bits..]) {
+                    *bit = (*bit & !segment_start) | (count & segment_start);
+                }
+                self.round[index] = decrement(&mut remaining) & !self.cube[index];
+                previous_cube = self.cube[index];
+            }
+        }
+    }
+}
+
+/// Adds one to the counters whose bit is set in `ones`
+fn increment(counter: &mut [u64], ones: u64) {
+    let mut carry = ones;
+    for bits in counter.iter_mut() {
+        let sum = *bits ^ carry;
+        carry &= *bits;
+        *bits = sum;
+    }
+}
+
+/// Subtracts one from the counters that are not zero and returns them
+fn decrement(counter: &mut [u64]) -> u64 {
+    let non_zero = counter.iter().fold(0, |acc, bits| acc | bits);
+    let mut borrow = non_zero;
+    for bits in counter.iter_mut() {
+        let difference = *bits ^ borrow;
+        borrow &= !*bits;
+        *bits = difference;
+    }
+    non_zero
+}
+
+/// The masks selecting the bits of `range` in each word they span
+fn word_masks(range: Range<usize>) -> impl Iterator<Item = (usize, u64)> {
+    let words = if range.is_empty() { 0..0 } else { range.start / 64..(range.end - 1) / 64 + 1 };
+    words.map(move |word| {
+        let low = range.start.max(word * 64) - word * 64;
+        let high = range.end.min(word * 64 + 64) - word * 64;
+        let mask = if high - low == 64 { !0 } else { ((1 << (high - low)) - 1) << low };
+        (word, mask)
+    })
+}
+
+fn count_ones(bits: &[u64], range: Range<usize>) -> usize {
+    word_masks(range).map(|(word, mask)| (bits[word] & mask).count_ones() as usize).sum()
+}
+
+fn set_range(bits: &mut [u64], range: Range<usize>, value: bool) {
+    for (word, mask) in word_masks(range) {
+        if value {
+            bits[word] |= mask;
         } else {
-            Self::from_columns(&new_lines)
+            bits[word] &= !mask;
         }
     }
 }
@@ -73,65 +208,66 @@ impl Tile {
     }
 }
 
-impl ToString for Platform {
-    fn to_string(&self) -> String {
-        let rows: Vec<String> = self.rows.iter().map(|row| row.iter().map(|tile| tile.as_char()).collect::<String>()).collect();
-        rows.join("\n")
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows: Vec<String> = (0..self.height).map(|row| (0..self.width).map(|column| self.tile(row, column).as_char()).collect()).collect();
+        write!(f, "{}", rows.join("\n"))
     }
 }
 
-fn tilt(line: &Vec<Tile>, to_start: bool) -> Vec<Tile> {
-    let mut new_line = line.clone();
-    let mut tiles: Box<dyn DoubleEndedIterator<Item = (usize, &Tile)>> = Box::new(line.iter().enumerate());
-    if !to_start {
-        tiles = Box::new(tiles.rev());
-    }
-    let line_len = line.len();
-    let default_position = if to_start { 0 } else { line_len - 1 };
-    for (index, tile) in tiles {
-        if *tile == Tile::RoundRock {
-            let mut new_position = None;
-            let scan_range: Box<dyn DoubleEndedIterator<Item = usize>> = if to_start {
-                Box::new((0..index).rev())
-            } else {
-                Box::new(index+1..line_len)
-            };
-            for scan_index in  scan_range {
-                if new_line[scan_index] != Tile::Empty {
-                    new_position = if to_start { Some(scan_index + 1) } else { Some(scan_index - 1) } ;
-                    break;
-                }
-            }
-
-            let new_position = new_position.unwrap_or(default_position);
-            if new_position != index {
-                new_line[index] = Tile::Empty;
-                new_line[new_position] = Tile::RoundRock;
-            }
-        }
-    }
-    new_line
-}
-
 impl Platform {
+    /// The load on the north support beams
     pub fn load(&self) -> usize {
+        self.load_on(Direction::North)
+    }
+
+    /// The load against an edge, each round rock weighing its distance to the opposite edge plus one
+    pub fn load_on(&self, edge: Direction) -> usize {
         let mut load = 0;
-        let len = self.rows.len();
-        for (row_index, row) in self.rows.iter().enumerate() {
-            for tile in row {
-                if *tile == Tile::RoundRock {
-                    load += len - row_index;
+        for row in 0..self.height {
+            for (word, &bits) in self.round[row * self.words_per_row..(row + 1) * self.words_per_row].iter().enumerate() {
+                let mut remaining = bits;
+                while remaining != 0 {
+                    let column = word * 64 + remaining.trailing_zeros() as usize;
+                    remaining &= remaining - 1;
+                    load += match edge {
+                        Direction::North => self.height - row,
+                        Direction::South => row + 1,
+                        Direction::West => self.width - column,
+                        Direction::East => column + 1,
+                    };
                 }
             }
         }
         load
     }
+
+    /// Tilts the platform north, west, south and then east
+    pub fn cycle(self) -> Self {
+        self.apply(&TiltProgram::spin_cycle())
+    }
+
+    /// The load on the north beams after `cycles` spin cycles
+    pub fn load_after(&self, cycles: usize) -> usize {
+        self.after(&TiltProgram::spin_cycle(), cycles).load()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const INPUT: &str = "O....#....
+O.OO#....#
+.....##...
+OO.#O....O
+.O.....O#.
+O.#..O.#.#
+..O..#O..O
+.......O..
+#....###..
+#OO..#....";
+
     #[test]
     fn test_tilt_north() {
         let input = "O....#....
@@ -177,6 +313,43 @@ O.#..O.#.#
         assert_eq!(tilted.load(), expected_output);
     }
 
+    #[test]
+    fn test_tilt_all_directions() {
+        let platform = Platform::parse("O.#O.\n..O.O\n#O..O");
+        assert_eq!(platform.clone().tilt(Direction::West).to_string(), "O.#O.\nOO...\n#OO..");
+        assert_eq!(platform.clone().tilt(Direction::East).to_string(), ".O#.O\n...OO\n#..OO");
+        assert_eq!(platform.clone().tilt(Direction::North).to_string(), "OO#OO\n..O.O\n#....");
+        assert_eq!(platform.tilt(Direction::South).to_string(), "..#..\nO...O\n#OOOO");
+    }
+
+    #[test]
+    fn test_wide_platform() {
+        let row: String = (0..150).map(|column| if column % 50 == 10 { '#' } else if column % 3 == 0 { 'O' } else { '.' }).collect();
+        let platform = Platform::parse(&format!("{}\n{}", row, row.replace('O', ".")));
+        let tilted = platform.clone().tilt(Direction::South).tilt(Direction::East);
+        assert_eq!(tilted.tile(1, 149), Tile::RoundRock);
+        assert_eq!(tilted.tile(0, 149), Tile::Empty);
+        assert_eq!(tilted.tilt(Direction::North).load(), platform.load());
+    }
+
+    #[test]
+    fn test_load_after() {
+        let platform = Platform::parse(INPUT);
+        assert_eq!(platform.load_after(0), 104);
+        assert_eq!(platform.load_after(1), platform.clone().cycle().load());
+        assert_eq!(platform.load_after(1_000_000_000), 64);
+        assert_eq!(platform.clone().cycle().state(), platform.cycle().state());
+    }
+
+    #[test]
+    fn test_load_on() {
+        let platform = Platform::parse("O.#\n..O");
+        assert_eq!(platform.load_on(Direction::North), 2 + 1);
+        assert_eq!(platform.load_on(Direction::South), 1 + 2);
+        assert_eq!(platform.load_on(Direction::West), 3 + 1);
+        assert_eq!(platform.load_on(Direction::East), 1 + 3);
+    }
+
     // Add more tests here
 }
 
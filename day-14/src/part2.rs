@@ -1,53 +1,10 @@
-use std::{collections::HashMap, hash::{DefaultHasher, Hash, Hasher}};
-
-use crate::common::{Direction, Platform};
+use crate::common::Platform;
 
 pub fn run(input: &str) -> String {
-    let mut platform = Platform::parse(input);
-    let num_cycles = 1_000_000_000;
-    
-    let hash = |platform: &Platform| {
-        let mut hasher = DefaultHasher::new();
-        platform.to_string().hash(&mut hasher);
-        hasher.finish()
-    };
-    let mut hashes = HashMap::new();
-    hashes.insert(hash(&platform), 0);
-    let mut repetition = None;
-    for i in 0..num_cycles {
-        platform = platform.cycle();
-        let hash = hash(&platform);
-        if hashes.contains_key(&hash) {
-            repetition = Some((*hashes.get(&hash).unwrap(), i));
-            break;
-        };
-        hashes.insert(hash, i);
-    }
-    if let Some((first, second)) = repetition {
-       println!("Cycle {first} {second}");
-       let number_of_cycles_to_repeat = second - first;
-       let remaining_cycles = num_cycles - second;
-       let remainder_after_cycle = remaining_cycles % number_of_cycles_to_repeat;
-        for _ in 0..remainder_after_cycle-1 {
-            platform = platform.cycle();
-        }
-    } 
-    platform.load().to_string()
+    let platform = Platform::parse(input);
+    platform.load_after(1_000_000_000).to_string()
 }
 
-impl Platform {
-    fn cycle(self) -> Self {
-        let mut tilted = self;
-        let all_directions = vec![Direction::North, Direction::West, Direction::South, Direction::East];
-        for direction in all_directions {
-            tilted = tilted.tilt(direction)
-        }
-        tilted
-    }
-}
-
-
-
 #[cfg(test)]
 mod tests {
     use super::*;
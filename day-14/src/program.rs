@@ -0,0 +1,161 @@
+use std::{collections::HashMap, fmt};
+
+use crate::common::{Direction, Platform};
+
+/// A sequence of tilts, written with one letter per direction, e.g. `NWSE` for a spin cycle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TiltProgram {
+    pub directions: Vec<Direction>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ProgramError {
+    InvalidDirection(char),
+}
+
+/// Where running a program over and over leads a platform: the states met before
+/// entering a loop, and the states of that loop which are then repeated forever
+#[derive(Debug, Clone)]
+pub struct SteadyStates {
+    /// The number of runs before the first state of the loop is reached
+    pub transient: usize,
+    pub states: Vec<Platform>,
+}
+
+impl Direction {
+    pub fn parse(char: char) -> Option<Self> {
+        match char {
+            'N' => Some(Self::North),
+            'S' => Some(Self::South),
+            'W' => Some(Self::West),
+            'E' => Some(Self::East),
+            _ => None,
+        }
+    }
+}
+
+impl TiltProgram {
+    /// Parses the letters N, S, W and E, whitespace is ignored
+    pub fn parse(input: &str) -> Result<Self, ProgramError> {
+        let directions = input
+            .chars()
+            .filter(|char| !char.is_whitespace())
+            .map(|char| Direction::parse(char).ok_or(ProgramError::InvalidDirection(char)))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { directions })
+    }
+
+    /// The program of the puzzle: north, west, south and then east
+    pub fn spin_cycle() -> Self {
+        Self { directions: vec![Direction::North, Direction::West, Direction::South, Direction::East] }
+    }
+
+    /// The program made of this one repeated `times` times
+    pub fn repeated(&self, times: usize) -> Self {
+        Self { directions: self.directions.repeat(times) }
+    }
+}
+
+impl fmt::Display for ProgramError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProgramError::InvalidDirection(char) => write!(f, "Invalid direction: {}", char),
+        }
+    }
+}
+
+impl SteadyStates {
+    /// The number of runs after which the steady states repeat
+    pub fn period(&self) -> usize {
+        self.states.len()
+    }
+}
+
+impl Platform {
+    /// Runs each tilt of the program once
+    pub fn apply(mut self, program: &TiltProgram) -> Self {
+        for &direction in &program.directions {
+            self.tilt_in_place(direction);
+        }
+        self
+    }
+
+    /// The platform after running the program `runs` times.
+    /// The states end up repeating, so only the runs until the first repetition are simulated.
+    pub fn after(&self, program: &TiltProgram, runs: usize) -> Platform {
+        let (mut platforms, loop_start) = self.trajectory(program, runs);
+        let index = match loop_start {
+            Some(first) if runs >= platforms.len() => first + (runs - first) % (platforms.len() - first),
+            _ => runs,
+        };
+        platforms.swap_remove(index)
+    }
+
+    pub fn steady_states(&self, program: &TiltProgram) -> SteadyStates {
+        let (platforms, loop_start) = self.trajectory(program, usize::MAX);
+        let transient = loop_start.expect("A platform has a finite number of states");
+        SteadyStates { transient, states: platforms.into_iter().skip(transient).collect() }
+    }
+
+    /// The platforms met while running the program up to `runs` times, stopping at the first repeated state.
+    /// Also returns the index of the platform that was repeated, if any.
+    fn trajectory(&self, program: &TiltProgram, runs: usize) -> (Vec<Platform>, Option<usize>) {
+        let mut platforms = vec![self.clone()];
+        let mut seen = HashMap::from([(self.state(), 0)]);
+        for run in 1..=runs {
+            let platform = platforms[run - 1].clone().apply(program);
+            if let Some(&first) = seen.get(&platform.state()) {
+                return (platforms, Some(first));
+            }
+            seen.insert(platform.state(), run);
+            platforms.push(platform);
+        }
+        (platforms, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = "O....#....
+O.OO#....#
+.....##...
+OO.#O....O
+.O.....O#.
+O.#..O.#.#
+..O..#O..O
+.......O..
+#....###..
+#OO..#....";
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(TiltProgram::parse("N W S E"), Ok(TiltProgram::spin_cycle()));
+        assert_eq!(TiltProgram::parse("NWX"), Err(ProgramError::InvalidDirection('X')));
+        assert_eq!(TiltProgram::parse("NNWSE").unwrap().repeated(2), TiltProgram::parse("NNWSENNWSE").unwrap());
+    }
+
+    #[test]
+    fn test_after() {
+        let platform = Platform::parse(INPUT);
+        let spin_cycle = TiltProgram::spin_cycle();
+        assert_eq!(platform.after(&spin_cycle, 1_000_000_000).load(), 64);
+        assert_eq!(platform.after(&spin_cycle, 3).to_string(), platform.clone().apply(&spin_cycle.repeated(3)).to_string());
+        let program = TiltProgram::parse("NNWSE").unwrap();
+        let direct = (0..20).fold(platform.clone(), |platform, _| platform.apply(&program));
+        assert_eq!(platform.after(&program, 20).state(), direct.state());
+    }
+
+    #[test]
+    fn test_steady_states() {
+        let platform = Platform::parse(INPUT);
+        let steady = platform.steady_states(&TiltProgram::spin_cycle());
+        assert_eq!((steady.transient, steady.period()), (3, 7));
+        let loads: Vec<usize> = steady.states.iter().map(|platform| platform.load()).collect();
+        assert!(loads.contains(&64));
+        let steady = platform.steady_states(&TiltProgram::parse("N").unwrap());
+        assert_eq!((steady.transient, steady.period()), (1, 1));
+        assert_eq!(steady.states[0].load_on(Direction::North), 136);
+    }
+}
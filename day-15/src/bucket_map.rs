@@ -0,0 +1,160 @@
+use std::hash::{BuildHasher, Hash};
+
+use crate::common::HolidayHasher;
+
+/// A map whose entries are kept in a fixed number of buckets, like the boxes of the lens puzzle.
+/// Each bucket keeps its entries in insertion order: replacing a value keeps its slot,
+/// and removing an entry moves the following ones forward.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BucketMap<K, V, S = HolidayHasher> {
+    buckets: Vec<Vec<(K, V)>>,
+    hasher: S,
+}
+
+impl<K: Hash + Eq, V> BucketMap<K, V> {
+    /// A map with one bucket for each value of the HASH algorithm
+    pub fn new() -> Self {
+        Self::with_hasher(256, HolidayHasher::default())
+    }
+}
+
+impl<K: Hash + Eq, V> Default for BucketMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> BucketMap<K, V, S> {
+    /// A map with `bucket_count` buckets, keys being sent to the bucket `hash % bucket_count`
+    pub fn with_hasher(bucket_count: usize, hasher: S) -> Self {
+        assert!(bucket_count > 0, "A bucket map needs at least one bucket");
+        Self { buckets: (0..bucket_count).map(|_| Vec::new()).collect(), hasher }
+    }
+
+    pub fn bucket_index(&self, key: &K) -> usize {
+        (self.hasher.hash_one(key) % self.buckets.len() as u64) as usize
+    }
+
+    /// Replaces the value of the key if it is already in the map, otherwise adds it at the end of its bucket.
+    /// Returns the value that was replaced.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let bucket_index = self.bucket_index(&key);
+        let bucket = &mut self.buckets[bucket_index];
+        match bucket.iter_mut().find(|(existing, _)| *existing == key) {
+            Some((_, existing)) => Some(std::mem::replace(existing, value)),
+            None => {
+                bucket.push((key, value));
+                None
+            }
+        }
+    }
+
+    /// Adds the key at the given slot of its bucket, or at its end if the bucket is shorter.
+    /// Replaces the value in place if the key is already in the map.
+    pub fn insert_at(&mut self, key: K, value: V, slot: usize) -> Option<V> {
+        if self.get(&key).is_some() {
+            return self.insert(key, value);
+        }
+        let bucket_index = self.bucket_index(&key);
+        let bucket = &mut self.buckets[bucket_index];
+        bucket.insert(slot.min(bucket.len()), (key, value));
+        None
+    }
+
+    /// Removes the key from its bucket, keeping the other entries in order
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let bucket_index = self.bucket_index(key);
+        let bucket = &mut self.buckets[bucket_index];
+        let slot = bucket.iter().position(|(existing, _)| existing == key)?;
+        Some(bucket.remove(slot).1)
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.buckets[self.bucket_index(key)].iter().find(|(existing, _)| existing == key).map(|(_, value)| value)
+    }
+
+    /// The bucket and the slot in the bucket of a key
+    pub fn position(&self, key: &K) -> Option<(usize, usize)> {
+        let bucket_index = self.bucket_index(key);
+        let slot = self.buckets[bucket_index].iter().position(|(existing, _)| existing == key)?;
+        Some((bucket_index, slot))
+    }
+}
+
+impl<K, V, S> BucketMap<K, V, S> {
+    pub fn len(&self) -> usize {
+        self.buckets.iter().map(|bucket| bucket.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buckets.iter().all(|bucket| bucket.is_empty())
+    }
+
+    /// The entries of each bucket, with the index of the bucket
+    pub fn buckets(&self) -> impl Iterator<Item = (usize, &[(K, V)])> {
+        self.buckets.iter().map(|bucket| bucket.as_slice()).enumerate()
+    }
+
+    /// The entries, bucket after bucket
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.buckets.iter().flatten().map(|(key, value)| (key, value))
+    }
+
+    /// Sums the weight of each entry multiplied by one plus its bucket index and by one plus its slot,
+    /// e.g. the focusing power when the weight is the focal length of a lens
+    pub fn weighted_sum<F>(&self, weight: F) -> u64
+    where
+        F: Fn(&K, &V) -> u64,
+    {
+        let weight = &weight;
+        self.buckets()
+            .flat_map(|(bucket_index, bucket)| {
+                bucket.iter().enumerate().map(move |(slot, (key, value))| (bucket_index as u64 + 1) * (slot as u64 + 1) * weight(key, value))
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::hash_map::RandomState;
+
+    use super::*;
+    use crate::common::Label;
+
+    #[test]
+    fn test_insert_and_remove() {
+        let mut map = BucketMap::new();
+        assert_eq!(map.insert(Label::new("rn"), 1), None);
+        assert_eq!(map.insert(Label::new("cm"), 2), None);
+        assert_eq!(map.insert(Label::new("qp"), 3), None);
+        assert_eq!(map.position(&Label::new("cm")), Some((0, 1)));
+        assert_eq!(map.insert(Label::new("rn"), 4), Some(1));
+        assert_eq!(map.position(&Label::new("rn")), Some((0, 0)));
+        assert_eq!(map.remove(&Label::new("rn")), Some(4));
+        assert_eq!(map.remove(&Label::new("rn")), None);
+        assert_eq!(map.position(&Label::new("cm")), Some((0, 0)));
+        let entries: Vec<(&str, i32)> = map.iter().map(|(label, &value)| (label.as_str(), value)).collect();
+        assert_eq!(entries, vec![("cm", 2), ("qp", 3)]);
+        assert_eq!(map.weighted_sum(|_, &value| value as u64), 2 + 2 * 3);
+        map.insert_at(Label::new("rn"), 5, 0);
+        assert_eq!(map.position(&Label::new("cm")), Some((0, 1)));
+        assert_eq!(map.insert_at(Label::new("cm"), 6, 0), Some(2));
+        assert_eq!(map.position(&Label::new("cm")), Some((0, 1)));
+    }
+
+    #[test]
+    fn test_other_hasher() {
+        let mut map = BucketMap::with_hasher(4, RandomState::new());
+        for number in 0..100u32 {
+            map.insert(number, number * 2);
+        }
+        map.insert(7, 0);
+        assert_eq!(map.len(), 100);
+        assert_eq!(map.get(&7), Some(&0));
+        assert_eq!(map.get(&50), Some(&100));
+        assert_eq!(map.buckets().count(), 4);
+        assert!((0..100).all(|number| map.remove(&number).is_some()));
+        assert!(map.is_empty());
+    }
+}
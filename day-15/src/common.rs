@@ -1,5 +1,163 @@
+use std::{fmt, hash::{BuildHasher, Hash, Hasher}};
+
+use crate::bucket_map::BucketMap;
+
+/// The HASH algorithm of the puzzle: for each byte, add it, multiply by 17 and keep the remainder by 256.
+/// It is also its own `BuildHasher`, every hasher starting from 0.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct HolidayHasher {
+    current_value: u8,
+}
+
+impl Hasher for HolidayHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.current_value = self.current_value.wrapping_add(byte).wrapping_mul(17);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.current_value as u64
+    }
+}
+
+impl BuildHasher for HolidayHasher {
+    type Hasher = HolidayHasher;
+
+    fn build_hasher(&self) -> HolidayHasher {
+        HolidayHasher::default()
+    }
+}
+
 pub fn hash(input: &str) -> u32 {
-    input.chars().map(|char| char as u32).fold(0u32, |acc, val|{
-        ((acc + val)*17) % 256
-    })
-}
\ No newline at end of file
+    let mut hasher = HolidayHasher::default();
+    hasher.write(input.as_bytes());
+    hasher.finish() as u32
+}
+
+/// The label of a lens. `str` feeds an extra byte to the hasher to separate consecutive strings,
+/// a label only feeds its own bytes so that it is hashed like the puzzle expects.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Label(String);
+
+impl Label {
+    pub fn new(label: &str) -> Self {
+        Self(label.to_string())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Hash for Label {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write(self.0.as_bytes());
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstructionType {
+    RemoveLens,
+    AssignLens{focal_length: u8}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    pub label: Label,
+    pub instruction_type: InstructionType
+}
+
+impl Instruction {
+    pub fn parse(input: &str) -> Self {
+        assert!(!input.is_empty());
+        if let Some(label) = input.strip_suffix("-") {
+            Self { label: Label::new(label), instruction_type: InstructionType::RemoveLens }
+        } else {
+            let (label, focal_length) = input.split_once("=").unwrap();
+            let instruction_type = InstructionType::AssignLens { focal_length: focal_length.parse().unwrap() };
+            Self { label: Label::new(label), instruction_type }
+        }
+    }
+
+    /// Parses the comma separated instructions, ignoring newlines
+    pub fn parse_sequence(input: &str) -> Vec<Self> {
+        input.replace("\n", "").split(",").map(Self::parse).collect()
+    }
+}
+
+/// The 256 boxes of the lens puzzle, mapping the label of each lens to its focal length
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BoxSeries {
+    pub boxes: BucketMap<Label, u8>,
+}
+
+impl BoxSeries {
+    pub fn new() -> Self {
+        Self { boxes: BucketMap::new() }
+    }
+
+    pub fn follow_instruction(&mut self, instruction: Instruction) {
+        match instruction.instruction_type {
+            InstructionType::AssignLens { focal_length } => {
+                self.boxes.insert(instruction.label, focal_length);
+            }
+            InstructionType::RemoveLens => {
+                self.boxes.remove(&instruction.label);
+            }
+        }
+    }
+
+    pub fn focusing_power(&self) -> u64 {
+        self.boxes.weighted_sum(|_, &focal_length| focal_length as u64)
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.instruction_type {
+            InstructionType::RemoveLens => write!(f, "{}-", self.label.as_str()),
+            InstructionType::AssignLens { focal_length } => write!(f, "{}={}", self.label.as_str(), focal_length),
+        }
+    }
+}
+
+/// Lists the boxes that hold lenses like the puzzle walkthrough, e.g. `Box 0: [rn 1] [cm 2]`
+impl fmt::Display for BoxSeries {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lines: Vec<String> = self
+            .boxes
+            .buckets()
+            .filter(|(_, lenses)| !lenses.is_empty())
+            .map(|(box_index, lenses)| {
+                let lenses: Vec<String> = lenses.iter().map(|(label, focal_length)| format!("[{} {}]", label.as_str(), focal_length)).collect();
+                format!("Box {}: {}", box_index, lenses.join(" "))
+            })
+            .collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_holiday_hasher() {
+        assert_eq!(hash("HASH"), 52);
+        assert_eq!(HolidayHasher::default().hash_one(Label::new("rn")), 0);
+        assert_eq!(HolidayHasher::default().hash_one(Label::new("qp")), 1);
+        assert_ne!(HolidayHasher::default().hash_one("qp"), 1);
+    }
+
+    #[test]
+    fn test_box_series() {
+        let mut box_series = BoxSeries::new();
+        for instruction in Instruction::parse_sequence("rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7") {
+            box_series.follow_instruction(instruction);
+        }
+        let box_3: Vec<(&str, u8)> = box_series.boxes.buckets().nth(3).unwrap().1.iter().map(|(label, focal_length)| (label.as_str(), *focal_length)).collect();
+        assert_eq!(box_3, vec![("ot", 7), ("ab", 5), ("pc", 6)]);
+        assert_eq!(box_series.focusing_power(), 145);
+    }
+}
@@ -1,4 +1,6 @@
 pub mod part1;
 pub mod part2;
+pub mod bucket_map;
+pub mod replay;
 
-mod common;
\ No newline at end of file
+pub mod common;
\ No newline at end of file
@@ -0,0 +1,110 @@
+use crate::common::{BoxSeries, Instruction, Label};
+
+/// Applies instructions one at a time, remembering enough to undo them
+pub struct Replay {
+    instructions: Vec<Instruction>,
+    state: BoxSeries,
+    /// For each applied instruction, the slot and focal length its lens had before it, if it was in a box
+    undo_log: Vec<Option<(usize, u8)>>,
+}
+
+impl Replay {
+    pub fn new(instructions: Vec<Instruction>) -> Self {
+        Self { instructions, state: BoxSeries::new(), undo_log: Vec::new() }
+    }
+
+    pub fn state(&self) -> &BoxSeries {
+        &self.state
+    }
+
+    /// The number of instructions applied so far
+    pub fn steps_done(&self) -> usize {
+        self.undo_log.len()
+    }
+
+    /// Applies the next instruction and returns the resulting state, or None once every instruction was applied
+    pub fn step(&mut self) -> Option<&BoxSeries> {
+        let instruction = self.instructions.get(self.steps_done())?.clone();
+        let previous = self.lens(&instruction.label);
+        self.state.follow_instruction(instruction);
+        self.undo_log.push(previous);
+        Some(&self.state)
+    }
+
+    /// Reverts the last applied instruction and returns the resulting state, or None if no instruction was applied
+    pub fn undo(&mut self) -> Option<&BoxSeries> {
+        let previous = self.undo_log.pop()?;
+        let label = self.instructions[self.steps_done()].label.clone();
+        match previous {
+            Some((slot, focal_length)) => self.state.boxes.insert_at(label, focal_length, slot),
+            None => self.state.boxes.remove(&label),
+        };
+        Some(&self.state)
+    }
+
+    /// The state after each of the remaining instructions
+    pub fn states(&mut self) -> impl Iterator<Item = BoxSeries> + '_ {
+        std::iter::from_fn(move || self.step().cloned())
+    }
+
+    /// The index of the last applied instruction about the label
+    pub fn last_touched(&self, label: &Label) -> Option<usize> {
+        self.instructions[..self.steps_done()].iter().rposition(|instruction| instruction.label == *label)
+    }
+
+    /// Applies the remaining instructions, describing the state after each one like the puzzle walkthrough
+    pub fn transcript(&mut self) -> String {
+        let mut steps = Vec::new();
+        while self.step().is_some() {
+            let instruction = &self.instructions[self.steps_done() - 1];
+            steps.push(format!("After \"{}\":\n{}", instruction, self.state));
+        }
+        steps.join("\n\n")
+    }
+
+    fn lens(&self, label: &Label) -> Option<(usize, u8)> {
+        let (_, slot) = self.state.boxes.position(label)?;
+        Some((slot, *self.state.boxes.get(label)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = "rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7";
+
+    #[test]
+    fn test_transcript() {
+        let mut replay = Replay::new(Instruction::parse_sequence(INPUT));
+        let transcript = replay.transcript();
+        assert!(transcript.starts_with("After \"rn=1\":\nBox 0: [rn 1]\n\nAfter \"cm-\":\nBox 0: [rn 1]\n\nAfter \"qp=3\":\nBox 0: [rn 1]\nBox 1: [qp 3]\n\n"));
+        assert!(transcript.ends_with("After \"ot=7\":\nBox 0: [rn 1] [cm 2]\nBox 3: [ot 7] [ab 5] [pc 6]"));
+        assert_eq!(replay.state().focusing_power(), 145);
+    }
+
+    #[test]
+    fn test_states_and_last_touched() {
+        let mut replay = Replay::new(Instruction::parse_sequence(INPUT));
+        let powers: Vec<u64> = replay.states().map(|state| state.focusing_power()).collect();
+        assert_eq!(powers.len(), 11);
+        assert_eq!(powers[2], 1 + 2 * 3);
+        assert_eq!(replay.last_touched(&Label::new("pc")), Some(9));
+        assert_eq!(replay.last_touched(&Label::new("xx")), None);
+        replay.undo();
+        assert_eq!(replay.last_touched(&Label::new("ot")), Some(6));
+    }
+
+    #[test]
+    fn test_undo() {
+        let instructions = Instruction::parse_sequence(INPUT);
+        let mut replay = Replay::new(instructions.clone());
+        let states: Vec<BoxSeries> = replay.states().collect();
+        for step in (0..instructions.len()).rev() {
+            let expected = if step == 0 { BoxSeries::new() } else { states[step - 1].clone() };
+            assert_eq!(replay.undo(), Some(&expected));
+        }
+        assert_eq!(replay.undo(), None);
+        assert_eq!(replay.step().map(|state| state.to_string()), Some("Box 0: [rn 1]".to_string()));
+    }
+}
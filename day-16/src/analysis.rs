@@ -0,0 +1,306 @@
+use std::collections::HashMap;
+
+use crate::common::{BeamHead, Contraption, Direction, Tile};
+
+/// A set of positions of a contraption, stored as a bitset
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TileSet {
+    width: usize,
+    words: Vec<u64>,
+}
+
+/// The beams of a contraption seen as a graph whose nodes are the beams hitting an element, like a mirror or a splitter.
+/// Beams travel in a straight line between two nodes, so each edge carries the empty tiles it crosses.
+/// Loops of beams are condensed into strongly connected components, whose energized tiles are all the same,
+/// and the energized tiles of each component are computed once from those of the components it leads to.
+pub struct BeamAnalysis<'a> {
+    contraption: &'a Contraption,
+    node_index: HashMap<BeamHead, usize>,
+    component_of: Vec<usize>,
+    component_tiles: Vec<TileSet>,
+}
+
+/// The edges going out of a node
+struct Node {
+    /// The tiles energized before reaching the next nodes, including the tile of the node
+    tiles: Vec<(usize, usize)>,
+    next: Vec<BeamHead>,
+}
+
+impl TileSet {
+    fn new(width: usize, height: usize) -> Self {
+        Self { width, words: vec![0; (width * height).div_ceil(64)] }
+    }
+
+    fn insert(&mut self, (row, column): (usize, usize)) {
+        let index = row * self.width + column;
+        self.words[index / 64] |= 1 << (index % 64);
+    }
+
+    fn union_with(&mut self, other: &TileSet) {
+        self.words.iter_mut().zip(&other.words).for_each(|(word, other)| *word |= other);
+    }
+
+    pub fn contains(&self, (row, column): (usize, usize)) -> bool {
+        let index = row * self.width + column;
+        column < self.width && self.words.get(index / 64).is_some_and(|word| word >> (index % 64) & 1 == 1)
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&word| word == 0)
+    }
+
+    /// The positions in the set, row by row
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.words.iter().enumerate().flat_map(move |(word_index, &word)| {
+            (0..64).filter(move |bit| word >> bit & 1 == 1).map(move |bit| {
+                let index = word_index * 64 + bit;
+                (index / self.width, index % self.width)
+            })
+        })
+    }
+}
+
+impl Contraption {
+    pub fn height(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub fn width(&self) -> usize {
+        self.tiles.first().map_or(0, |row| row.len())
+    }
+
+    /// Every beam entering the contraption from one of its edges
+    pub fn entry_beams(&self) -> Vec<BeamHead> {
+        let (height, width) = (self.height(), self.width());
+        let right_beams = (0..height).map(|row| BeamHead { position: (row, 0), direction: Direction::Right });
+        let left_beams = (0..height).map(|row| BeamHead { position: (row, width - 1), direction: Direction::Left });
+        let down_beams = (0..width).map(|column| BeamHead { position: (0, column), direction: Direction::Down });
+        let up_beams = (0..width).map(|column| BeamHead { position: (height - 1, column), direction: Direction::Up });
+        right_beams.chain(left_beams).chain(down_beams).chain(up_beams).collect()
+    }
+
+    /// The analysis of the beams, None if the contraption has stateful elements
+    pub fn beam_analysis(&self) -> Option<BeamAnalysis<'_>> {
+        BeamAnalysis::new(self)
+    }
+
+    /// Follows a beam through empty tiles until it hits another element, or leaves the contraption.
+    /// Returns the empty tiles it crossed and the beam hitting the next element.
+    fn follow(&self, beam: BeamHead) -> (Vec<(usize, usize)>, Option<BeamHead>) {
+        let bounds = (self.height(), self.width());
+        let mut tiles = Vec::new();
+        let mut current = Some(beam);
+        while let Some(beam) = current {
+            if !matches!(self.tiles[beam.position.0][beam.position.1], Tile::Empty) {
+                return (tiles, Some(beam));
+            }
+            tiles.push(beam.position);
+            current = beam.extend(beam.direction, bounds);
+        }
+        (tiles, None)
+    }
+
+    fn node(&self, beam: &BeamHead) -> Node {
+        let bounds = (self.height(), self.width());
+        let exit = self.exit(beam.position);
+        let mut node = Node { tiles: vec![beam.position, exit], next: Vec::new() };
+        let tile = &self.tiles[beam.position.0][beam.position.1];
+        for direction in tile.affect_direction(&beam.direction, 0) {
+            let leaving = BeamHead { position: exit, direction };
+            if let Some(beam) = leaving.extend(direction, bounds) {
+                let (tiles, next) = self.follow(beam);
+                node.tiles.extend(tiles);
+                node.next.extend(next);
+            }
+        }
+        node
+    }
+}
+
+impl<'a> BeamAnalysis<'a> {
+    /// None if the contraption has stateful elements, which don't always send a beam the same way
+    pub fn new(contraption: &'a Contraption) -> Option<Self> {
+        if contraption.is_stateful() {
+            return None;
+        }
+        let mut node_beams = Vec::new();
+        for (row, tiles) in contraption.tiles.iter().enumerate() {
+            for (column, tile) in tiles.iter().enumerate() {
+                if !matches!(tile, Tile::Empty) {
+                    let directions = [Direction::Up, Direction::Down, Direction::Right, Direction::Left];
+                    node_beams.extend(directions.map(|direction| BeamHead { position: (row, column), direction }));
+                }
+            }
+        }
+        let node_index: HashMap<BeamHead, usize> = node_beams.iter().enumerate().map(|(index, beam)| (beam.clone(), index)).collect();
+        let nodes: Vec<Node> = node_beams.iter().map(|beam| contraption.node(beam)).collect();
+        let successors: Vec<Vec<usize>> = nodes.iter().map(|node| node.next.iter().map(|beam| node_index[beam]).collect()).collect();
+        let components = strongly_connected_components(&successors);
+        let mut component_of = vec![0; nodes.len()];
+        for (component_index, component) in components.iter().enumerate() {
+            component.iter().for_each(|&node| component_of[node] = component_index);
+        }
+        // The components come out in reverse topological order, so the components a component leads to are already done
+        let mut component_tiles: Vec<TileSet> = Vec::with_capacity(components.len());
+        for (component_index, component) in components.iter().enumerate() {
+            let mut tiles = TileSet::new(contraption.width(), contraption.height());
+            for &node in component {
+                nodes[node].tiles.iter().for_each(|&position| tiles.insert(position));
+                for &next in &successors[node] {
+                    if component_of[next] != component_index {
+                        tiles.union_with(&component_tiles[component_of[next]]);
+                    }
+                }
+            }
+            component_tiles.push(tiles);
+        }
+        Some(Self { contraption, node_index, component_of, component_tiles })
+    }
+
+    /// The tiles energized by a beam starting at any position of the contraption
+    pub fn energized(&self, beam: &BeamHead) -> TileSet {
+        let (tiles, next) = self.contraption.follow(beam.clone());
+        let mut energized = match next {
+            Some(next) => self.component_tiles[self.component_of[self.node_index[&next]]].clone(),
+            None => TileSet::new(self.contraption.width(), self.contraption.height()),
+        };
+        tiles.into_iter().for_each(|position| energized.insert(position));
+        energized
+    }
+
+    /// The entry beam energizing the most tiles, with the tiles it energizes, None if the contraption is empty
+    pub fn best_entry(&self) -> Option<(BeamHead, TileSet)> {
+        self.contraption
+            .entry_beams()
+            .into_iter()
+            .map(|beam| {
+                let energized = self.energized(&beam);
+                (beam, energized)
+            })
+            .max_by_key(|(_, energized)| energized.len())
+    }
+}
+
+/// Tarjan's algorithm, without recursion so that long chains of beams don't overflow the stack.
+/// The components are returned in reverse topological order.
+fn strongly_connected_components(successors: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let node_count = successors.len();
+    let mut index = vec![usize::MAX; node_count];
+    let mut low_link = vec![0; node_count];
+    let mut on_stack = vec![false; node_count];
+    let mut stack = Vec::new();
+    let mut components = Vec::new();
+    let mut next_index = 0;
+    for root in 0..node_count {
+        if index[root] != usize::MAX {
+            continue;
+        }
+        let mut call_stack = vec![(root, 0)];
+        while let Some(&(node, edge)) = call_stack.last() {
+            if edge == 0 && index[node] == usize::MAX {
+                index[node] = next_index;
+                low_link[node] = next_index;
+                next_index += 1;
+                stack.push(node);
+                on_stack[node] = true;
+            }
+            if let Some(&next) = successors[node].get(edge) {
+                call_stack.last_mut().unwrap().1 += 1;
+                if index[next] == usize::MAX {
+                    call_stack.push((next, 0));
+                } else if on_stack[next] {
+                    low_link[node] = low_link[node].min(index[next]);
+                }
+                continue;
+            }
+            call_stack.pop();
+            if let Some(&(parent, _)) = call_stack.last() {
+                low_link[parent] = low_link[parent].min(low_link[node]);
+            }
+            if low_link[node] == index[node] {
+                let mut component = Vec::new();
+                while let Some(member) = stack.pop() {
+                    on_stack[member] = false;
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                components.push(component);
+            }
+        }
+    }
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    const INPUT: &str = r".|...\....
+|.-.\.....
+.....|-...
+........|.
+..........
+.........\
+..../.\\..
+.-.-/..|..
+.|....-|.\
+..//.|....";
+
+    #[test]
+    fn test_matches_flood_fill() {
+        let contraption = Contraption::parse(INPUT);
+        let analysis = contraption.beam_analysis().unwrap();
+        for beam in contraption.entry_beams() {
+            let expected: HashSet<(usize, usize)> = contraption.energize(beam.clone()).into_iter().collect();
+            let energized: HashSet<(usize, usize)> = analysis.energized(&beam).positions().collect();
+            assert_eq!(energized, expected, "{:?}", beam);
+        }
+    }
+
+    #[test]
+    fn test_best_entry() {
+        let contraption = Contraption::parse(INPUT);
+        let (beam, energized) = contraption.beam_analysis().unwrap().best_entry().unwrap();
+        assert_eq!(beam, BeamHead { position: (0, 3), direction: Direction::Down });
+        assert_eq!(energized.len(), 51);
+        assert!(energized.contains((9, 5)));
+        assert!(!energized.contains((0, 0)));
+    }
+
+    #[test]
+    fn test_extended_elements() {
+        let contraption = Contraption::parse(r".a.\..
+..7|.#
+a.L.-F
+.J..\.");
+        let analysis = contraption.beam_analysis().unwrap();
+        for beam in contraption.entry_beams() {
+            let expected: HashSet<(usize, usize)> = contraption.energize(beam.clone()).into_iter().collect();
+            let energized: HashSet<(usize, usize)> = analysis.energized(&beam).positions().collect();
+            assert_eq!(energized, expected, "{:?}", beam);
+        }
+    }
+
+    #[test]
+    fn test_stateful_contraption() {
+        assert!(Contraption::parse("...\n.+.\n...").beam_analysis().is_none());
+    }
+
+    #[test]
+    fn test_up_entries_start_at_bottom() {
+        let contraption = Contraption::parse("..\n./\n..");
+        let up_beams: Vec<BeamHead> = contraption.entry_beams().into_iter().filter(|beam| beam.direction == Direction::Up).collect();
+        assert_eq!(up_beams[1], BeamHead { position: (2, 1), direction: Direction::Up });
+        let energized = contraption.beam_analysis().unwrap().energized(&up_beams[1]);
+        assert_eq!(energized.positions().collect::<Vec<_>>(), vec![(1, 1), (2, 1)]);
+    }
+}
@@ -1,15 +1,28 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 pub struct Contraption {
-    pub tiles: Vec<Vec<Tile>>
+    pub tiles: Vec<Vec<Tile>>,
+    /// For each portal, the position of the other portal with the same letter
+    pub portals: HashMap<(usize, usize), (usize, usize)>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Tile {
     Empty,
     Vertical,
     Horizontal,
     Slash,
-    BackSlash
+    BackSlash,
+    /// A diagonal mirror with a single reflective side, facing the two directions.
+    /// `F` faces down and right, `7` down and left, `J` up and left and `L` up and right.
+    /// Beams hitting the other side go through.
+    OneWayMirror(Direction, Direction),
+    /// `#` stops every beam
+    Absorber,
+    /// `+` splits beams like `|` when it is in state 0 and like `-` in state 1, and switches state each time it's hit
+    ToggleSplitter,
+    /// A lowercase letter, beams hitting it leave from the other portal with the same letter in the same direction
+    Portal(char),
 }
 
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
@@ -34,13 +47,44 @@ impl Tile {
             '-' => Self::Horizontal,
             '/' => Self::Slash,
             '\\' => Self::BackSlash,
+            'F' => Self::OneWayMirror(Direction::Down, Direction::Right),
+            '7' => Self::OneWayMirror(Direction::Down, Direction::Left),
+            'J' => Self::OneWayMirror(Direction::Up, Direction::Left),
+            'L' => Self::OneWayMirror(Direction::Up, Direction::Right),
+            '#' => Self::Absorber,
+            '+' => Self::ToggleSplitter,
+            'a'..='z' => Self::Portal(char),
             _ => panic!("Invalid tile {char}")
         }
     }
 
-    fn affect_direction(&self, direction: &Direction) -> Vec<Direction> {
+    /// The number of states the element switches between, each time a beam hits it
+    pub fn state_count(&self) -> usize {
+        match self {
+            Self::ToggleSplitter => 2,
+            _ => 1,
+        }
+    }
+
+    /// The directions of the beams leaving the tile when a beam going in `direction` hits it in the given state
+    pub fn affect_direction(&self, direction: &Direction, state: usize) -> Vec<Direction> {
         match (self, direction) {
-            (Self::Empty, _) => vec![*direction],
+            (Self::Empty | Self::Portal(_), _) => vec![*direction],
+            (Self::Absorber, _) => vec![],
+            (Self::ToggleSplitter, _) => {
+                let splitter = if state == 0 { Self::Vertical } else { Self::Horizontal };
+                splitter.affect_direction(direction, 0)
+            }
+            (Self::OneWayMirror(first, second), _) => {
+                // A beam hits the reflective side when it comes from one of the directions it faces
+                if *direction == first.opposite() {
+                    vec![*second]
+                } else if *direction == second.opposite() {
+                    vec![*first]
+                } else {
+                    vec![*direction]
+                }
+            }
             
             (Self::Horizontal, Direction::Left | Direction::Right) => vec![*direction],
             (Self::Horizontal, Direction::Up | Direction::Down) => vec![Direction::Left, Direction::Right],
@@ -64,39 +108,84 @@ impl Tile {
 
 impl Contraption {
     pub fn parse(input: &str) -> Self {
-        let tiles = input
+        let tiles: Vec<Vec<Tile>> = input
         .lines()
-        .map(|line| line.chars().map(|char|Tile::parse(char)).collect())
+        .map(|line| line.chars().map(Tile::parse).collect())
         .collect();
-        Self { tiles }
+        let mut portals_by_letter: HashMap<char, Vec<(usize, usize)>> = HashMap::new();
+        for (row, line) in tiles.iter().enumerate() {
+            for (column, tile) in line.iter().enumerate() {
+                if let Tile::Portal(letter) = tile {
+                    portals_by_letter.entry(*letter).or_default().push((row, column));
+                }
+            }
+        }
+        let mut portals = HashMap::new();
+        for (letter, positions) in portals_by_letter {
+            match positions[..] {
+                [first, second] => {
+                    portals.insert(first, second);
+                    portals.insert(second, first);
+                }
+                _ => panic!("Portal {letter} appears {} times instead of twice", positions.len()),
+            }
+        }
+        Self { tiles, portals }
     }
 
+    /// Whether some elements behave differently depending on how many beams hit them before
+    pub fn is_stateful(&self) -> bool {
+        self.tiles.iter().flatten().any(|tile| tile.state_count() > 1)
+    }
+
+    /// The position the beams hitting a tile leave from, which is the tile itself unless it's a portal
+    pub fn exit(&self, position: (usize, usize)) -> (usize, usize) {
+        self.portals.get(&position).copied().unwrap_or(position)
+    }
+
+    /// Follows the beams through the contraption and returns the energized positions.
+    /// Stateful elements switch state each time they're hit, in the order the beams reach them.
+    /// A beam hitting an element in a state it was already hit in by the same beam is dropped, which is an approximation:
+    /// only the state of that element is compared, so the beam is dropped even if other stateful elements changed state
+    /// since and would now send it another way.
     pub fn energize(&self, original_beam: BeamHead) -> Vec<(usize, usize)> {
-        let mut working_beams: Vec<BeamHead> = Vec::new();
+        let mut working_beams: Vec<BeamHead> = vec![original_beam];
         let mut past_beams = HashSet::new();
+        let mut states: HashMap<(usize, usize), usize> = HashMap::new();
+        let mut exits = HashSet::new();
         let bounds = (self.tiles.len(), self.tiles[0].len());
-        past_beams.insert(original_beam.clone());
-        working_beams.push(original_beam);
-        while working_beams.len() > 0 {
-            let beam = working_beams.pop().unwrap();
+        while let Some(beam) = working_beams.pop() {
             let tile = &self.tiles[beam.position.0][beam.position.1];
-            for new_direction in tile.affect_direction(&beam.direction) {
-                let new_beam = beam.extend(new_direction, bounds);
-                if let Some(new_beam) = new_beam {
-                    if !past_beams.contains(&new_beam) {
-                        past_beams.insert(new_beam.clone());
-                        working_beams.push(new_beam);
-                    }
-                }
+            let state = states.entry(beam.position).or_insert(0);
+            if !past_beams.insert((beam.clone(), *state)) {
+                continue;
+            }
+            let new_directions = tile.affect_direction(&beam.direction, *state);
+            *state = (*state + 1) % tile.state_count();
+            let exit = self.exit(beam.position);
+            exits.insert(exit);
+            for new_direction in new_directions {
+                let new_beam = BeamHead { position: exit, direction: beam.direction }.extend(new_direction, bounds);
+                working_beams.extend(new_beam);
             }
         }
-        let energized_positions = past_beams.into_iter().map(|beam| beam.position).collect::<HashSet<_>>().into_iter().collect();
-        energized_positions
+        past_beams.into_iter().map(|(beam, _)| beam.position).chain(exits).collect::<HashSet<_>>().into_iter().collect()
+    }
+}
+
+impl Direction {
+    pub fn opposite(&self) -> Self {
+        match self {
+            Self::Up => Self::Down,
+            Self::Down => Self::Up,
+            Self::Right => Self::Left,
+            Self::Left => Self::Right,
+        }
     }
 }
 
 impl BeamHead {
-    fn extend(&self, new_direction: Direction, bounds: (usize, usize)) -> Option<Self> {
+    pub fn extend(&self, new_direction: Direction, bounds: (usize, usize)) -> Option<Self> {
         let (max_row, max_column) = bounds;
         let (row, column) = self.position;
         let new_position = match (new_direction, row, column) {
@@ -113,4 +202,49 @@ impl BeamHead {
             Self{position: new_position, direction: new_direction}
         })
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn energized(input: &str, beam: BeamHead) -> Vec<(usize, usize)> {
+        let mut positions = Contraption::parse(input).energize(beam);
+        positions.sort();
+        positions
+    }
+
+    fn from_left(row: usize) -> BeamHead {
+        BeamHead { position: (row, 0), direction: Direction::Right }
+    }
+
+    #[test]
+    fn test_one_way_mirror() {
+        assert_eq!(energized("..7\n...", from_left(0)), vec![(0, 0), (0, 1), (0, 2), (1, 2)]);
+        assert_eq!(energized("..F\n...", from_left(0)), vec![(0, 0), (0, 1), (0, 2)]);
+        assert_eq!(energized(".J.", from_left(0)), vec![(0, 0), (0, 1)]);
+        let from_below = BeamHead { position: (1, 0), direction: Direction::Up };
+        assert_eq!(energized("F.\n..", from_below), vec![(0, 0), (0, 1), (1, 0)]);
+    }
+
+    #[test]
+    fn test_absorber() {
+        assert_eq!(energized(".#.", from_left(0)), vec![(0, 0), (0, 1)]);
+    }
+
+    #[test]
+    fn test_portals() {
+        assert_eq!(energized(".a.\n...\n..a", from_left(0)), vec![(0, 0), (0, 1), (2, 2)]);
+        assert_eq!(energized(".b..b.", from_left(0)), vec![(0, 0), (0, 1), (0, 4), (0, 5)]);
+    }
+
+    #[test]
+    fn test_toggle_splitter() {
+        // The beam goes through the splitter upwards, comes back to it from the right and goes through again
+        let input = "./\\\n.+/\n...";
+        let beam = BeamHead { position: (2, 1), direction: Direction::Up };
+        assert_eq!(energized(input, beam), vec![(0, 1), (0, 2), (1, 0), (1, 1), (1, 2), (2, 1)]);
+        assert!(Contraption::parse(input).is_stateful());
+        assert!(!Contraption::parse(".|-").is_stateful());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::common::{BeamHead, Contraption, Direction};
+use crate::common::Contraption;
 
 pub fn run(input: &str) -> String {
     let contraption = Contraption::parse(input);
@@ -8,17 +8,13 @@ pub fn run(input: &str) -> String {
 
 impl Contraption {
     fn find_max_energize(&self) -> usize {
-        let max_row = self.tiles.len();
-        let max_column = self.tiles[0].len();
-        let right_beams = (0..max_row).map(|row| BeamHead{position: (row, 0), direction: Direction::Right});
-        let left_beams = (0..max_row).map(|row| BeamHead{position: (row, max_column - 1), direction: Direction::Left});
-        let down_beams = (0..max_column).map(|column| BeamHead{ position: (0, column), direction: Direction::Down});
-        let up_beams = (0..max_column).map(|column| BeamHead{ position: (0, column), direction: Direction::Up});
-        let source_beams = right_beams.chain(left_beams).chain(up_beams).chain(down_beams);
-        let max = source_beams.map(|source_beam| self.energize(source_beam).len()).max();
-        max.unwrap()
+        match self.beam_analysis() {
+            Some(analysis) => analysis.best_entry().map(|(_, energized)| energized.len()).unwrap(),
+            // Stateful elements don't always send a beam the same way, so each entry beam is followed on its own
+            None => self.entry_beams().into_iter().map(|beam| self.energize(beam).len()).max().unwrap(),
+        }
     }
-}   
+}
 
 #[cfg(test)]
 mod tests {
@@ -40,5 +36,10 @@ mod tests {
         assert_eq!(run(input), expected_output);
     }
 
+    #[test]
+    fn test_run_stateful() {
+        assert_eq!(run("...\n.+.\n..."), "4");
+    }
+
     // Add more tests here
 }
\ No newline at end of file
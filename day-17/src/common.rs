@@ -1,28 +1,99 @@
-use std::collections::{HashMap, HashSet};
+use std::{cmp::Reverse, collections::{BinaryHeap, HashMap}};
 
 pub struct HeatMap {
     values: Vec<Vec<u32>>,
     dimensions: (usize, usize)
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum Direction {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Direction {
     Up, Down, Left, Right
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
-struct Node {
-    row: usize,
-    col: usize,
-    dir: Direction,
+pub type Position = (usize, usize);
+
+/// A state of the crucible: where it stopped, and the direction it was moving in.
+/// The direction is None at the start, before the crucible moved.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+pub struct Node {
+    pub row: usize,
+    pub col: usize,
+    pub dir: Option<Direction>,
 }
 
+/// How the crucible can move. It moves in straight lines of `min_blocks` to `max_blocks` blocks,
+/// and must turn between two straight lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MoveRules {
     pub min_blocks: u32,
-    pub max_bocks: u32
+    pub max_blocks: u32,
+    /// The heat lost each time the crucible changes direction
+    pub turn_penalty: u32,
+    /// Whether the crucible can reverse direction
+    pub allow_u_turns: bool,
+    /// The (min, max) blocks replacing `min_blocks` and `max_blocks` when moving in some directions
+    pub direction_limits: HashMap<Direction, (u32, u32)>,
+}
+
+/// A straight line of blocks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Move {
+    pub direction: Direction,
+    pub blocks: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    /// Every cell the crucible goes through, including the start and the end
+    pub cells: Vec<Position>,
+    pub moves: Vec<Move>,
+    pub heat_loss: u32,
 }
 
-const ALL_DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+/// What Dijkstra's algorithm found when it stopped. The heat losses are only the least ones for the nodes it was done with,
+/// which are all the reachable nodes when it didn't stop at an end node.
+#[derive(Debug, Clone)]
+pub struct ShortestPaths {
+    pub distances: HashMap<Node, u32>,
+    /// The node before each node on its best path found so far
+    pub predecessors: HashMap<Node, Node>,
+    pub end: Option<Node>,
+}
+
+pub const ALL_DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
+impl MoveRules {
+    pub fn new(min_blocks: u32, max_blocks: u32) -> Self {
+        Self { min_blocks, max_blocks, turn_penalty: 0, allow_u_turns: false, direction_limits: HashMap::new() }
+    }
+
+    pub fn with_turn_penalty(mut self, turn_penalty: u32) -> Self {
+        self.turn_penalty = turn_penalty;
+        self
+    }
+
+    pub fn with_u_turns(mut self) -> Self {
+        self.allow_u_turns = true;
+        self
+    }
+
+    pub fn with_direction_limits(mut self, direction: Direction, min_blocks: u32, max_blocks: u32) -> Self {
+        self.direction_limits.insert(direction, (min_blocks, max_blocks));
+        self
+    }
+
+    /// The (min, max) blocks the crucible can move in a direction
+    pub fn limits(&self, direction: Direction) -> (u32, u32) {
+        self.direction_limits.get(&direction).copied().unwrap_or((self.min_blocks, self.max_blocks))
+    }
+
+    fn can_turn(&self, from: Option<Direction>, to: Direction) -> bool {
+        match from {
+            None => true,
+            Some(from) => from != to && (self.allow_u_turns || !from.is_opposite(&to)),
+        }
+    }
+}
 
 impl HeatMap {
     pub fn parse(input: &str) -> Self {
@@ -31,128 +102,174 @@ impl HeatMap {
         Self { values, dimensions }
     }
 
+    pub fn dimensions(&self) -> (usize, usize) {
+        self.dimensions
+    }
+
     fn edge_value(&self, node: &Node) -> u32 {
         self.values[node.row][node.col]
     }
 
     fn move_node(&self, node: &Node, dir: Direction, steps: usize) -> Option<Node> {
         let (max_row, max_column) = self.dimensions;
-        return match (dir, node.row, node.col) {
+        let dir_node = Some(dir);
+        match (dir, node.row, node.col) {
             (Direction::Up, row, _) if row < steps => None,
-            (Direction::Up, row, col) => Some(Node { row: row - steps, col, dir }),
+            (Direction::Up, row, col) => Some(Node { row: row - steps, col, dir: dir_node }),
             (Direction::Down, row, _) if row + steps >= max_row => None,
-            (Direction::Down, row, col) => Some(Node { row: row + steps, col, dir }),
+            (Direction::Down, row, col) => Some(Node { row: row + steps, col, dir: dir_node }),
             (Direction::Left, _, col) if col < steps => None,
-            (Direction::Left, row, col) => Some(Node { row, col: col - steps, dir }),
+            (Direction::Left, row, col) => Some(Node { row, col: col - steps, dir: dir_node }),
             (Direction::Right, _, col) if col + steps >= max_column => None,
-            (Direction::Right, row, col) => Some(Node { row, col: col + steps, dir }),
-        };
+            (Direction::Right, row, col) => Some(Node { row, col: col + steps, dir: dir_node }),
+        }
     }
 
-    fn get_neighbors(&self, node: &Node, rules: &MoveRules) -> Vec<(Node, u32)> {
+    /// The nodes reachable with a single straight line, with the heat lost on the way
+    pub fn get_neighbors(&self, node: &Node, rules: &MoveRules) -> Vec<(Node, u32)> {
         let mut neighbors = Vec::new();
-        for &dir in ALL_DIRECTIONS.iter().filter(|dir| !node.dir.eq(dir) && !node.dir.is_opposite(dir)) {
-            let mut edge_cost = 0;
-            for steps in 1..=rules.max_bocks {
-                if let Some(neighbor) = self.move_node(node, dir, steps as usize) {
-                    let edge_value =  self.edge_value(&neighbor);
-                    edge_cost += edge_value;
-                    if steps >= rules.min_blocks {
-                        neighbors.push((neighbor, edge_cost));
-                    }
-                } else {
-                    continue; // We hit the edge
+        for &dir in ALL_DIRECTIONS.iter().filter(|&&dir| rules.can_turn(node.dir, dir)) {
+            let (min_blocks, max_blocks) = rules.limits(dir);
+            let mut edge_cost = if node.dir.is_some() { rules.turn_penalty } else { 0 };
+            for steps in 1..=max_blocks {
+                let Some(neighbor) = self.move_node(node, dir, steps as usize) else {
+                    break; // We hit the edge
+                };
+                edge_cost += self.edge_value(&neighbor);
+                if steps >= min_blocks {
+                    neighbors.push((neighbor, edge_cost));
                 }
             }
         }
-        // println!("Node: {:?} - Neighbors: {:?}", node, neighbors);
         neighbors
-        
     }
 
-    fn is_destination(&self, node: &Node) -> bool {
+    /// The route with the least heat loss from the top left corner to the bottom right one
+    pub fn find_minimal_heat_loss(&self, rules: &MoveRules) -> Option<Route> {
         let (max_row, max_col) = self.dimensions;
-        node.row == max_row - 1 && node.col == max_col - 1
-    }
-
-    pub fn find_minimal_heat_loss(&self, rules: &MoveRules) -> u32 {
-        let mut distances: HashMap<Node, u32> = HashMap::new();
-        let mut queue = Vec::new();
-        let mut predecessors: HashMap<Node, Node> = HashMap::new(); 
-        [Direction::Down, Direction::Right].iter().for_each(|&dir| {
-            let node = Node{row: 0, col: 0, dir};
-            distances.insert(node.clone(), 0);
-            queue.push(node);
-        });
-        // let mut visited: HashSet<Node> = HashSet::new(); 
-        let mut destination_reached = 0;
-        while !queue.is_empty() {
-            println!("queue len: {}", queue.len());
-            let index = queue.iter().enumerate().min_by(|(_,node1), (_, node2)| {
-                distances.get(&node1).unwrap().cmp(distances.get(&node2).unwrap())
-            }).map(|(index, _)| index).unwrap();
-            let current_node = queue.remove(index);
-            // visited.insert(current_node.clone());
-            let current_dist = *distances.get(&current_node).unwrap();
-            if self.is_destination(&current_node) {
-                destination_reached += 1;
-                println!("Destination reached: {}", destination_reached);
-                if destination_reached == 2 {
-                    break;
-                }
+        self.find_route((0, 0), (max_row - 1, max_col - 1), rules)
+    }
+
+    /// The route with the least heat loss between two cells, found with Dijkstra's algorithm over the nodes
+    pub fn find_route(&self, start: Position, end: Position, rules: &MoveRules) -> Option<Route> {
+        let start = Node { row: start.0, col: start.1, dir: None };
+        let (nodes, heat_loss) = self.search(start, rules, |node| (node.row, node.col) == end, |_, _| true)?;
+        Some(Route::from_nodes(&nodes, heat_loss))
+    }
+
+    /// Dijkstra's algorithm from a node, only following the edges accepted by `allowed`.
+    /// Returns the nodes of the best path to the first end node reached, with its heat loss.
+    pub fn search<E, A>(&self, start: Node, rules: &MoveRules, is_end: E, allowed: A) -> Option<(Vec<Node>, u32)>
+    where
+        E: Fn(&Node) -> bool,
+        A: Fn(&Node, &Node) -> bool,
+    {
+        let paths = self.shortest_paths(start, rules, is_end, allowed);
+        let end = paths.end?;
+        Some((self.path(&paths.predecessors, end), paths.distances[&end]))
+    }
+
+    /// Dijkstra's algorithm from a node, only following the edges accepted by `allowed`, and stopping at the first end node reached.
+    /// Without an end node, every reachable node is reached with its least heat loss.
+    pub fn shortest_paths<E, A>(&self, start: Node, rules: &MoveRules, is_end: E, allowed: A) -> ShortestPaths
+    where
+        E: Fn(&Node) -> bool,
+        A: Fn(&Node, &Node) -> bool,
+    {
+        let mut distances: HashMap<Node, u32> = HashMap::from([(start, 0)]);
+        let mut predecessors: HashMap<Node, Node> = HashMap::new();
+        let mut queue = BinaryHeap::from([Reverse((0, start))]);
+        while let Some(Reverse((current_dist, current_node))) = queue.pop() {
+            if current_dist > distances[&current_node] {
+                continue; // A shorter path to this node was already handled
+            }
+            if is_end(&current_node) {
+                return ShortestPaths { distances, predecessors, end: Some(current_node) };
             }
             for (neighbor, edge_cost) in self.get_neighbors(&current_node, rules) {
-                // if visited.contains(&neighbor){
-                //     continue;
-                // }
-                let new_dist = current_dist + edge_cost ;
-                let old_dist = distances.get(&neighbor);
-                if old_dist.is_none_or(|&old_dist| old_dist > new_dist ) {
-                    distances.insert(neighbor.clone(), new_dist);
-                    predecessors.insert(neighbor.clone(), current_node.clone());
-                    queue.push(neighbor);
+                if !allowed(&current_node, &neighbor) {
+                    continue;
+                }
+                let new_dist = current_dist + edge_cost;
+                if distances.get(&neighbor).is_none_or(|&old_dist| old_dist > new_dist) {
+                    distances.insert(neighbor, new_dist);
+                    predecessors.insert(neighbor, current_node);
+                    queue.push(Reverse((new_dist, neighbor)));
                 }
             }
-        } 
-        let (node, min) = distances.iter().filter(|(node, _)| self.is_destination(node)).min_by(|(_, dist1), (_, dist2)| dist1.cmp(dist2)).expect("We should have reached the destination");
-        // print_path(&predecessors, node, &distances);
-        return *min;
+        }
+        ShortestPaths { distances, predecessors, end: None }
     }
-}
 
-fn print_path(predecessors: &HashMap<Node, Node>, node: &Node, distances: &HashMap<Node, u32>) {
-    let mut current_node = node;
-    loop {
-        println!("({},{}) {}",current_node.row, current_node.col, distances.get(current_node).unwrap());
-        let predecessor = predecessors.get(current_node);
-        if predecessor.is_none() {
-            break;
+    fn path(&self, predecessors: &HashMap<Node, Node>, end: Node) -> Vec<Node> {
+        let mut nodes = vec![end];
+        while let Some(predecessor) = predecessors.get(nodes.last().unwrap()) {
+            nodes.push(*predecessor);
         }
-        current_node = predecessor.unwrap();
+        nodes.reverse();
+        nodes
+    }
+
+    /// Draws the heat map with the route on it, each cell of the route showing the direction the crucible entered it with
+    pub fn render(&self, route: &Route) -> String {
+        let mut grid: Vec<Vec<char>> = self.values.iter().map(|row| row.iter().map(|&value| char::from_digit(value, 10).unwrap()).collect()).collect();
+        let mut cells = route.cells.iter().skip(1);
+        for movement in &route.moves {
+            for &(row, col) in cells.by_ref().take(movement.blocks as usize) {
+                grid[row][col] = movement.direction.to_char();
+            }
+        }
+        grid.iter().map(|row| row.iter().collect::<String>()).collect::<Vec<_>>().join("\n")
     }
 }
 
+impl Route {
+    /// Builds the route going through the nodes in order
+    pub fn from_nodes(nodes: &[Node], heat_loss: u32) -> Self {
+        let mut cells = vec![(nodes[0].row, nodes[0].col)];
+        let mut moves = Vec::new();
+        for pair in nodes.windows(2) {
+            let direction = pair[1].dir.expect("Only the first node has no direction");
+            let blocks = pair[0].row.abs_diff(pair[1].row) + pair[0].col.abs_diff(pair[1].col);
+            for step in 1..=blocks {
+                let (row, col) = (pair[0].row, pair[0].col);
+                cells.push(match direction {
+                    Direction::Up => (row - step, col),
+                    Direction::Down => (row + step, col),
+                    Direction::Left => (row, col - step),
+                    Direction::Right => (row, col + step),
+                });
+            }
+            moves.push(Move { direction, blocks: blocks as u32 });
+        }
+        Self { cells, moves, heat_loss }
+    }
+}
 
 impl Direction {
     fn is_opposite(&self, other: &Direction) -> bool {
         match (self, other) {
             (Direction::Up, Direction::Down) => true,
-            (Direction::Down, Direction::Up) => true, 
+            (Direction::Down, Direction::Up) => true,
             (Direction::Left, Direction::Right) => true,
             (Direction::Right, Direction::Left) => true,
             _ => false
         }
     }
+
+    pub fn to_char(&self) -> char {
+        match self {
+            Direction::Up => '^',
+            Direction::Down => 'v',
+            Direction::Left => '<',
+            Direction::Right => '>',
+        }
+    }
 }
 
+/// The example of the puzzle, shared by the tests
 #[cfg(test)]
-mod tests {
-    use super::*;
-
-
-    #[test]
-    fn test_neighbors() {
-        let input = "2413432311323
+pub const INPUT: &str = "2413432311323
 3215453535623
 3255245654254
 3446585845452
@@ -165,10 +282,70 @@ mod tests {
 1224686865563
 2546548887735
 4322674655533";
-        let heat_map = HeatMap::parse(&input);
-        let node = Node{row: 0, col: 0, dir: Direction::Down};
-        let rules = MoveRules{max_bocks: 3, min_blocks: 1};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+
+    #[test]
+    fn test_neighbors() {
+        let heat_map = HeatMap::parse(INPUT);
+        let node = Node{row: 0, col: 0, dir: Some(Direction::Down)};
+        let rules = MoveRules::new(1, 3);
         let neighbors = heat_map.get_neighbors(&node, &rules);
-        println!("{:?}", neighbors)
+        let right = |col| Node { row: 0, col, dir: Some(Direction::Right) };
+        assert_eq!(neighbors, vec![(right(1), 4), (right(2), 5), (right(3), 8)]);
+        let neighbors = heat_map.get_neighbors(&node, &rules.clone().with_turn_penalty(10).with_u_turns());
+        assert_eq!(neighbors[0], (right(1), 14));
+        let start = Node { row: 0, col: 0, dir: None };
+        assert_eq!(heat_map.get_neighbors(&start, &rules).len(), 6);
+    }
+
+    #[test]
+    fn test_route() {
+        let heat_map = HeatMap::parse(INPUT);
+        let route = heat_map.find_minimal_heat_loss(&MoveRules::new(1, 3)).unwrap();
+        assert_eq!(route.heat_loss, 102);
+        assert_eq!(route.cells.first(), Some(&(0, 0)));
+        assert_eq!(route.cells.last(), Some(&(12, 12)));
+        let heat_loss: u32 = route.cells.iter().skip(1).map(|&(row, col)| heat_map.values[row][col]).sum();
+        assert_eq!(heat_loss, 102);
+        assert_eq!(route.moves.iter().map(|movement| movement.blocks as usize).sum::<usize>(), route.cells.len() - 1);
+        assert!(route.moves.iter().all(|movement| (1..=3).contains(&movement.blocks)));
+    }
+
+    #[test]
+    fn test_render() {
+        let heat_map = HeatMap::parse("19\n11");
+        let route = heat_map.find_minimal_heat_loss(&MoveRules::new(1, 3)).unwrap();
+        assert_eq!(route.moves, vec![Move { direction: Direction::Down, blocks: 1 }, Move { direction: Direction::Right, blocks: 1 }]);
+        assert_eq!(heat_map.render(&route), "19\nv>");
+    }
+
+    #[test]
+    fn test_start_and_end() {
+        let heat_map = HeatMap::parse(INPUT);
+        let route = heat_map.find_route((12, 12), (0, 0), &MoveRules::new(1, 3)).unwrap();
+        assert_eq!(route.cells.last(), Some(&(0, 0)));
+        assert_eq!(heat_map.find_route((3, 4), (3, 4), &MoveRules::new(1, 3)).unwrap().heat_loss, 0);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_alternative_rules() {
+        let heat_map = HeatMap::parse("111\n991\n111");
+        // Going around the 9 is cheaper, until turning costs more than going through it
+        let rules = MoveRules::new(1, 3).with_direction_limits(Direction::Down, 2, 2);
+        assert_eq!(heat_map.find_route((0, 0), (2, 0), &rules).unwrap().heat_loss, 1 + 1 + 1 + 1 + 1 + 1);
+        let rules = rules.with_turn_penalty(5);
+        let route = heat_map.find_route((0, 0), (2, 0), &rules).unwrap();
+        assert_eq!(route.moves, vec![Move { direction: Direction::Down, blocks: 2 }]);
+        assert_eq!(route.heat_loss, 9 + 1);
+        let straight = HeatMap::parse("1111");
+        let rules = MoveRules::new(3, 3);
+        assert_eq!(straight.find_route((0, 1), (0, 3), &rules), None);
+        let route = straight.find_route((0, 1), (0, 3), &rules.with_direction_limits(Direction::Left, 1, 1).with_u_turns()).unwrap();
+        assert_eq!(route.moves, vec![Move { direction: Direction::Left, blocks: 1 }, Move { direction: Direction::Right, blocks: 3 }]);
+        assert_eq!(route.heat_loss, 1 + 3);
+    }
+}
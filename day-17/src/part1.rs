@@ -3,9 +3,9 @@ use crate::common::{HeatMap, MoveRules};
 
 pub fn run(input: &str) -> String {
     let heat_map = HeatMap::parse(input);
-    let rules = MoveRules { min_blocks: 1, max_bocks: 3 };
-    let minimal_heat_loss = heat_map.find_minimal_heat_loss(&rules);
-    minimal_heat_loss.to_string()
+    let rules = MoveRules::new(1, 3);
+    let route = heat_map.find_minimal_heat_loss(&rules).expect("The destination should be reachable");
+    route.heat_loss.to_string()
 }
 
 
@@ -0,0 +1,148 @@
+use std::{cmp::Reverse, collections::{BinaryHeap, HashSet}, fmt};
+
+use crate::common::{HeatMap, MoveRules, Node, Position, Route};
+
+/// The least heat lost reaching each cell of a heat map, None for the cells the crucible can't stop on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeatLossMap {
+    pub values: Vec<Vec<Option<u32>>>,
+}
+
+impl HeatLossMap {
+    pub fn get(&self, (row, col): Position) -> Option<u32> {
+        self.values.get(row)?.get(col).copied().flatten()
+    }
+}
+
+impl fmt::Display for HeatLossMap {
+    /// One line per row, the values aligned in columns and the unreachable cells shown as `-`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.values.iter().flatten().flatten().map(|value| value.to_string().len()).max().unwrap_or(1);
+        let lines: Vec<String> = self
+            .values
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|value| match value {
+                        Some(value) => format!("{:>width$}", value),
+                        None => format!("{:>width$}", "-"),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+impl HeatMap {
+    /// The `k` routes with the least heat loss between two cells, best first, found with Yen's algorithm over the nodes.
+    /// Routes are different when the crucible stops at different nodes, and a route never stops twice at the same node.
+    pub fn k_best_routes(&self, start: Position, end: Position, rules: &MoveRules, k: usize) -> Vec<Route> {
+        let is_end = |node: &Node| (node.row, node.col) == end;
+        let start = Node { row: start.0, col: start.1, dir: None };
+        let Some(first) = self.search(start, rules, is_end, |_, _| true).filter(|_| k > 0) else {
+            return Vec::new();
+        };
+        let mut seen: HashSet<Vec<Node>> = HashSet::from([first.0.clone()]);
+        let mut best = vec![first];
+        let mut candidates = BinaryHeap::new();
+        while best.len() < k {
+            let previous = &best.last().unwrap().0;
+            for spur_index in 0..previous.len() - 1 {
+                let root = &previous[..=spur_index];
+                // The next routes must leave the root by an edge none of the best routes sharing that root took,
+                // and can't go back through the root
+                let banned_edges: HashSet<(Node, Node)> = best
+                    .iter()
+                    .filter(|(nodes, _)| nodes.len() > spur_index + 1 && nodes[..=spur_index] == *root)
+                    .map(|(nodes, _)| (nodes[spur_index], nodes[spur_index + 1]))
+                    .collect();
+                let banned_nodes: HashSet<Node> = root[..spur_index].iter().copied().collect();
+                let allowed = |from: &Node, to: &Node| !banned_nodes.contains(to) && !banned_edges.contains(&(*from, *to));
+                let Some((spur, spur_heat_loss)) = self.search(root[spur_index], rules, is_end, allowed) else {
+                    continue;
+                };
+                let mut nodes = root[..spur_index].to_vec();
+                nodes.extend(spur);
+                if seen.insert(nodes.clone()) {
+                    candidates.push(Reverse((self.heat_loss(root, rules) + spur_heat_loss, nodes)));
+                }
+            }
+            let Some(Reverse((heat_loss, nodes))) = candidates.pop() else {
+                break; // There are fewer than k routes
+            };
+            best.push((nodes, heat_loss));
+        }
+        best.into_iter().map(|(nodes, heat_loss)| Route::from_nodes(&nodes, heat_loss)).collect()
+    }
+
+    /// The least heat lost going from the start to each cell of the map
+    pub fn minimal_heat_losses(&self, start: Position, rules: &MoveRules) -> HeatLossMap {
+        let (rows, cols) = self.dimensions();
+        let mut values = vec![vec![None; cols]; rows];
+        let start = Node { row: start.0, col: start.1, dir: None };
+        for (node, distance) in self.shortest_paths(start, rules, |_| false, |_, _| true).distances {
+            let value: &mut Option<u32> = &mut values[node.row][node.col];
+            *value = Some(value.map_or(distance, |value| value.min(distance)));
+        }
+        HeatLossMap { values }
+    }
+
+    /// The heat lost following the nodes in order
+    fn heat_loss(&self, nodes: &[Node], rules: &MoveRules) -> u32 {
+        nodes
+            .windows(2)
+            .map(|pair| {
+                let neighbors = self.get_neighbors(&pair[0], rules);
+                neighbors.into_iter().find(|(neighbor, _)| *neighbor == pair[1]).expect("Consecutive nodes are neighbors").1
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{Direction, Move, INPUT};
+
+    #[test]
+    fn test_k_best_routes() {
+        let heat_map = HeatMap::parse(INPUT);
+        let rules = MoveRules::new(1, 3);
+        let routes = heat_map.k_best_routes((0, 0), (12, 12), &rules, 10);
+        assert_eq!(routes.len(), 10);
+        assert_eq!(routes[0].heat_loss, 102);
+        assert!(routes.windows(2).all(|pair| pair[0].heat_loss <= pair[1].heat_loss));
+        for (index, route) in routes.iter().enumerate() {
+            assert!(routes[..index].iter().all(|other| other.moves != route.moves));
+            let heat_loss: u32 = route.cells.iter().skip(1).map(|&(row, col)| INPUT.lines().nth(row).unwrap().as_bytes()[col] as u32 - '0' as u32).sum();
+            assert_eq!(heat_loss, route.heat_loss);
+        }
+        assert!(heat_map.k_best_routes((0, 0), (12, 12), &rules, 0).is_empty());
+    }
+
+    #[test]
+    fn test_fewer_routes_than_asked() {
+        let heat_map = HeatMap::parse("12\n11");
+        let routes = heat_map.k_best_routes((0, 0), (1, 1), &MoveRules::new(1, 3), 5);
+        let moves: Vec<Vec<Move>> = routes.iter().map(|route| route.moves.clone()).collect();
+        let down = Move { direction: Direction::Down, blocks: 1 };
+        let right = Move { direction: Direction::Right, blocks: 1 };
+        assert_eq!(moves, vec![vec![down, right], vec![right, down]]);
+        assert_eq!(routes.iter().map(|route| route.heat_loss).collect::<Vec<_>>(), vec![2, 3]);
+    }
+
+    #[test]
+    fn test_minimal_heat_losses() {
+        let heat_map = HeatMap::parse(INPUT);
+        let losses = heat_map.minimal_heat_losses((0, 0), &MoveRules::new(1, 3));
+        assert_eq!(losses.get((0, 0)), Some(0));
+        assert_eq!(losses.get((12, 12)), Some(102));
+        assert_eq!(losses.get((0, 2)), Some(5));
+        assert_eq!(heat_map.minimal_heat_losses((0, 0), &MoveRules::new(4, 10)).get((12, 12)), Some(94));
+        let losses = HeatMap::parse("111\n191").minimal_heat_losses((0, 0), &MoveRules::new(2, 2));
+        assert_eq!(losses.to_string(), "0 - 2\n- - -");
+        assert_eq!(losses.get((1, 2)), None);
+    }
+}
@@ -16,7 +16,7 @@ pub struct System {
 }
 
 impl System {
-    fn parse(input: &str) -> Self {
+    pub fn parse(input: &str) -> Self {
         let workflows = input.lines().map(Workflow::parse).map(|w|(w.name.clone(), w)).collect();
         Self { workflows }
     }
@@ -185,7 +185,7 @@ impl Part {
         Self{ x, m, a, s }
     }
 
-    fn get_value(&self, category: &Category) -> u32 {
+    pub fn get_value(&self, category: &Category) -> u32 {
         match  category {
             Category::X => self.x,
             Category::M => self.m,
@@ -0,0 +1,353 @@
+use std::{collections::{HashMap, VecDeque}, fmt};
+
+use crate::common::{Category, Decision, Part, RuleCondition, RuleDestination, System, Workflow, ACCEPT, REJECT};
+use crate::region::Region;
+
+/// A node of a decision tree, testing whether a rating is lower than a bound
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TreeNode {
+    Leaf(Decision),
+    Branch { category: Category, bound: u32, below: usize, above: usize },
+}
+
+/// The whole system flattened into a single decision tree, in which identical subtrees are stored once.
+/// Tests whose outcome is known from the earlier tests are dropped, and so are the tests leading to the same subtree,
+/// so that equivalent systems of workflows tend to compile to the same tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecisionTree {
+    nodes: Vec<TreeNode>,
+    root: usize,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CompileError {
+    MissingStart,
+    UnknownWorkflow(String),
+    /// Some parts go through the workflow twice, and would loop forever
+    Cycle(String),
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompileError::MissingStart => write!(f, "No in workflow"),
+            CompileError::UnknownWorkflow(name) => write!(f, "Unknown workflow: {}", name),
+            CompileError::Cycle(name) => write!(f, "Parts loop through workflow {}", name),
+        }
+    }
+}
+
+struct Compiler<'a> {
+    system: &'a System,
+    nodes: Vec<TreeNode>,
+    node_index: HashMap<TreeNode, usize>,
+    path: Vec<&'a str>,
+}
+
+impl<'a> Compiler<'a> {
+    fn new(system: &'a System) -> Self {
+        let mut compiler = Self { system, nodes: Vec::new(), node_index: HashMap::new(), path: Vec::new() };
+        compiler.intern(TreeNode::Leaf(REJECT));
+        compiler.intern(TreeNode::Leaf(ACCEPT));
+        compiler
+    }
+
+    fn intern(&mut self, node: TreeNode) -> usize {
+        if let Some(&index) = self.node_index.get(&node) {
+            return index;
+        }
+        self.nodes.push(node);
+        self.node_index.insert(node, self.nodes.len() - 1);
+        self.nodes.len() - 1
+    }
+
+    fn branch(&mut self, category: Category, bound: u32, below: usize, above: usize) -> usize {
+        if below == above {
+            return below; // The test doesn't change the outcome
+        }
+        // Two tests of the category with the same outcome on one side merge into the test with the furthest bound
+        match self.nodes[above] {
+            TreeNode::Branch { category: next, bound: next_bound, below: next_below, .. } if next == category && next_bound > bound && next_below == below => {
+                return above;
+            }
+            _ => {}
+        }
+        match self.nodes[below] {
+            TreeNode::Branch { category: next, bound: next_bound, above: next_above, .. } if next == category && next_bound < bound && next_above == above => {
+                return below;
+            }
+            _ => {}
+        }
+        self.intern(TreeNode::Branch { category, bound, below, above })
+    }
+
+    fn workflow(&mut self, name: &'a str, region: Region) -> Result<usize, CompileError> {
+        if self.path.contains(&name) {
+            return Err(CompileError::Cycle(name.to_string()));
+        }
+        let workflow = self.system.workflows.get(name).ok_or_else(|| CompileError::UnknownWorkflow(name.to_string()))?;
+        self.path.push(name);
+        let node = self.rules(workflow, 0, region)?;
+        self.path.pop();
+        Ok(node)
+    }
+
+    /// The tree sorting the parts of the region reaching the rule `rule_index` of the workflow.
+    /// The parts matching none of the rules are rejected.
+    fn rules(&mut self, workflow: &'a Workflow, rule_index: usize, region: Region) -> Result<usize, CompileError> {
+        let Some(rule) = workflow.rules.get(rule_index) else {
+            return Ok(self.intern(TreeNode::Leaf(REJECT)));
+        };
+        let Some(condition) = &rule.condition else {
+            return self.destination(&rule.destination, region);
+        };
+        let (matching, others) = region.split(condition);
+        if matching.is_empty() {
+            return self.rules(workflow, rule_index + 1, others);
+        }
+        if others.is_empty() {
+            return self.destination(&rule.destination, matching);
+        }
+        let matched = self.destination(&rule.destination, matching)?;
+        let unmatched = self.rules(workflow, rule_index + 1, others)?;
+        // Every test is written `category < bound`, `category > bound` being `!(category < bound + 1)`
+        if condition.lower_than_bound {
+            Ok(self.branch(condition.category, condition.bound, matched, unmatched))
+        } else {
+            Ok(self.branch(condition.category, condition.bound + 1, unmatched, matched))
+        }
+    }
+
+    fn destination(&mut self, destination: &'a RuleDestination, region: Region) -> Result<usize, CompileError> {
+        match destination {
+            RuleDestination::Decision(decision) => Ok(self.intern(TreeNode::Leaf(*decision))),
+            RuleDestination::Workflow { name } => self.workflow(name, region),
+        }
+    }
+}
+
+impl System {
+    pub fn compile(&self) -> Result<DecisionTree, CompileError> {
+        if !self.workflows.contains_key("in") {
+            return Err(CompileError::MissingStart);
+        }
+        let mut compiler = Compiler::new(self);
+        let root = compiler.workflow("in", Region::full())?;
+        Ok(DecisionTree { nodes: compiler.nodes, root })
+    }
+}
+
+impl DecisionTree {
+    pub fn evaluate(&self, part: &Part) -> Decision {
+        let mut index = self.root;
+        loop {
+            match self.nodes[index] {
+                TreeNode::Leaf(decision) => return decision,
+                TreeNode::Branch { category, bound, below, above } => {
+                    index = if part.get_value(&category) < bound { below } else { above };
+                }
+            }
+        }
+    }
+
+    /// Disjoint regions covering exactly the accepted parts
+    pub fn accepted_regions(&self) -> Vec<Region> {
+        let mut regions = Vec::new();
+        let mut stack = vec![(self.root, Region::full())];
+        while let Some((index, region)) = stack.pop() {
+            match self.nodes[index] {
+                TreeNode::Leaf(decision) => {
+                    if decision == ACCEPT {
+                        regions.push(region);
+                    }
+                }
+                TreeNode::Branch { category, bound, below, above } => {
+                    let (below_region, above_region) = region.split(&RuleCondition { category, lower_than_bound: true, bound });
+                    stack.push((above, above_region));
+                    stack.push((below, below_region));
+                }
+            }
+        }
+        regions
+    }
+
+    /// The number of accepted parts with ratings from 1 to 4000
+    pub fn count_accepted(&self) -> u64 {
+        self.accepted_regions().iter().map(|region| region.count()).sum()
+    }
+
+    /// The number of nodes reachable from the root, leaves included
+    pub fn node_count(&self) -> usize {
+        let mut reached = vec![false; self.nodes.len()];
+        let mut stack = vec![self.root];
+        while let Some(index) = stack.pop() {
+            if !std::mem::replace(&mut reached[index], true) {
+                if let TreeNode::Branch { below, above, .. } = self.nodes[index] {
+                    stack.extend([below, above]);
+                }
+            }
+        }
+        reached.iter().filter(|&&reached| reached).count()
+    }
+
+    /// Writes the tree back as workflows, starting with `in`, with the fewest rules the tree allows.
+    /// Each test of the tree becomes one rule and each workflow ends with a rule without condition,
+    /// so the text is minimal when it has the fewest workflows: a subtree shared by several tests gets a single workflow,
+    /// and each test sends to its rule destination the outcome that is a decision or a shared workflow when it can,
+    /// so that its other outcome goes on in the same workflow instead of needing a new one.
+    pub fn to_minimal_workflows(&self) -> String {
+        let mut references = vec![0; self.nodes.len()];
+        let mut stack = vec![self.root];
+        while let Some(index) = stack.pop() {
+            if let TreeNode::Branch { below, above, .. } = self.nodes[index] {
+                for child in [below, above] {
+                    references[child] += 1;
+                    if references[child] == 1 {
+                        stack.push(child);
+                    }
+                }
+            }
+        }
+        let mut names: HashMap<usize, String> = HashMap::from([(self.root, "in".to_string())]);
+        let mut pending = VecDeque::from([self.root]);
+        let mut workflows = Vec::new();
+        while let Some(index) = pending.pop_front() {
+            let mut rules = Vec::new();
+            let mut current = index;
+            loop {
+                let TreeNode::Branch { category, bound, below, above } = self.nodes[current] else {
+                    rules.push(self.destination(current, &mut names, &mut pending));
+                    break;
+                };
+                let category = format!("{:?}", category).to_lowercase();
+                // Outcomes that are decisions or shared subtrees don't need a new workflow to be a rule destination
+                let referenced = |child: usize| matches!(self.nodes[child], TreeNode::Leaf(_)) || references[child] > 1;
+                let (rule, next) = if referenced(below) || !referenced(above) {
+                    (format!("{}<{}:{}", category, bound, self.destination(below, &mut names, &mut pending)), above)
+                } else {
+                    (format!("{}>{}:{}", category, bound - 1, self.destination(above, &mut names, &mut pending)), below)
+                };
+                rules.push(rule);
+                if references[next] > 1 {
+                    rules.push(self.destination(next, &mut names, &mut pending));
+                    break;
+                }
+                current = next;
+            }
+            workflows.push(format!("{}{{{}}}", names[&index], rules.join(",")));
+        }
+        workflows.join("\n")
+    }
+
+    /// How a rule refers to a node: the decision of a leaf, or the name of the workflow made for a branch
+    fn destination(&self, index: usize, names: &mut HashMap<usize, String>, pending: &mut VecDeque<usize>) -> String {
+        if let TreeNode::Leaf(decision) = self.nodes[index] {
+            return if decision == ACCEPT { "A" } else { "R" }.to_string();
+        }
+        if !names.contains_key(&index) {
+            names.insert(index, workflow_name(names.len() - 1));
+            pending.push_back(index);
+        }
+        names[&index].clone()
+    }
+}
+
+/// The `number`-th of the workflow names `a`, ..., `z`, `aa`, `ab`, ..., skipping `in` which is the name of the root
+fn workflow_name(number: usize) -> String {
+    const IN_NUMBER: usize = 26 * 9 + 13;
+    let mut number = if number >= IN_NUMBER { number + 1 } else { number };
+    let mut letters = Vec::new();
+    loop {
+        letters.push((b'a' + (number % 26) as u8) as char);
+        if number < 26 {
+            break;
+        }
+        number = number / 26 - 1;
+    }
+    letters.iter().rev().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::parse_input;
+
+    const INPUT: &str = "px{a<2006:qkq,m>2090:A,rfg}
+pv{a>1716:R,A}
+lnx{m>1548:A,A}
+rfg{s<537:gd,x>2440:R,A}
+qs{s>3448:A,lnx}
+qkq{x<1416:A,crn}
+crn{x>2662:A,R}
+in{s<1351:px,qqz}
+qqz{s>2770:qs,m<1801:hdj,R}
+gd{a>3333:R,R}
+hdj{m>838:A,pv}
+
+{x=787,m=2655,a=1222,s=2876}
+{x=1679,m=44,a=2067,s=496}
+{x=2036,m=264,a=79,s=2244}
+{x=2461,m=1339,a=466,s=291}
+{x=2127,m=1623,a=2188,s=1013}";
+
+    #[test]
+    fn test_evaluate() {
+        let (system, parts) = parse_input(INPUT);
+        let tree = system.compile().unwrap();
+        for part in &parts {
+            assert_eq!(tree.evaluate(part), system.check_part(part));
+        }
+        assert_eq!(tree.count_accepted(), 167409079868000);
+        let regions = tree.accepted_regions();
+        assert!(regions.iter().all(|region| !region.is_empty()));
+        assert!(parts.iter().all(|part| regions.iter().filter(|region| region.contains(part)).count() == tree.evaluate(part) as usize));
+    }
+
+    #[test]
+    fn test_merge_equivalent_branches() {
+        let system = System::parse("in{x<100:a,b}\na{m>5:A,A}\nb{s<10:R,x<50:A,a>1000:R,R}");
+        let tree = system.compile().unwrap();
+        assert_eq!(tree.node_count(), 3);
+        assert_eq!(tree.to_minimal_workflows(), "in{x<100:A,R}");
+    }
+
+    #[test]
+    fn test_to_minimal_workflows() {
+        let (system, parts) = parse_input(INPUT);
+        let tree = system.compile().unwrap();
+        let text = tree.to_minimal_workflows();
+        assert!(text.starts_with("in{"));
+        assert_eq!(text, "in{s<1351:a,s>2770:A,m>1800:R,m>838:A,a<1717:A,R}
+a{a<2006:b,m>2090:A,s<537:R,x<2441:A,R}
+b{x<1416:A,x<2663:R,A}");
+        let recompiled = System::parse(&text).compile().unwrap();
+        assert_eq!(recompiled.to_minimal_workflows(), text);
+        assert_eq!(recompiled.node_count(), tree.node_count());
+        assert_eq!(recompiled.count_accepted(), 167409079868000);
+        assert!(parts.iter().all(|part| recompiled.evaluate(part) == tree.evaluate(part)));
+    }
+
+    #[test]
+    fn test_shared_subtrees_stay_rule_destinations() {
+        let system = System::parse("in{x<100:p,s}\np{a<10:q,s}\nq{m<50:A,R}\ns{m<5:A,R}");
+        let text = system.compile().unwrap().to_minimal_workflows();
+        assert_eq!(text, "in{x>99:a,a>9:a,m<50:A,R}\na{m<5:A,R}");
+        assert_eq!(System::parse(&text).compile().unwrap().count_accepted(), system.compile().unwrap().count_accepted());
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(System::parse("a{A}").compile(), Err(CompileError::MissingStart));
+        assert_eq!(System::parse("in{x<10:zz,A}").compile(), Err(CompileError::UnknownWorkflow("zz".to_string())));
+        assert_eq!(System::parse("in{x<10:a,A}\na{in}").compile(), Err(CompileError::Cycle("in".to_string())));
+        let unmatched = System::parse("in{x<10:R,x>4000:A,a}\na{x>20:A}").compile().unwrap();
+        assert_eq!(unmatched.count_accepted(), (4000 - 20) * 4000u64.pow(3));
+        assert_eq!(unmatched.to_minimal_workflows(), "in{x<21:R,A}");
+        // The loop can't be taken, and the last rule of `a` is only reached by parts with x <= 20
+        assert!(System::parse("in{x<10:R,x>4000:in,a}\na{x>20:A,x<30:R}").compile().is_ok());
+        assert_eq!(workflow_name(0), "a");
+        assert_eq!(workflow_name(26), "aa");
+        assert_eq!(workflow_name(246), "im");
+        assert_eq!(workflow_name(247), "io");
+    }
+}
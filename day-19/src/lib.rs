@@ -1,4 +1,7 @@
 pub mod part1;
 pub mod part2;
+pub mod region;
+pub mod validation;
+pub mod compiler;
 
-mod common;
\ No newline at end of file
+pub mod common;
\ No newline at end of file
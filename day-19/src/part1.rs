@@ -2,7 +2,11 @@ use crate::common::{self, Part};
 
 pub fn run(input: &str) -> String {
     let (system, parts) = common::parse_input(input);
-    let accepted_parts : Vec<&Part> = parts.iter().filter(|part| system.check_part(part)).collect();
+    let accepted_parts : Vec<&Part> = match system.compile() {
+        Ok(tree) => parts.iter().filter(|part| tree.evaluate(part)).collect(),
+        // An unknown workflow may only be on branches none of the listed parts take, so they are checked one by one
+        Err(_) => parts.iter().filter(|part| system.check_part(part)).collect(),
+    };
     let total: u32 = accepted_parts.iter().map(|part| part.total_rating()).sum();
     return total.to_string()
 }
@@ -34,4 +38,10 @@ hdj{m>838:A,pv}
         let expected_output = "19114";
         assert_eq!(run(input), expected_output);
     }
-}
+
+    #[test]
+    fn test_run_unknown_workflow() {
+        let input = "in{x<1000:zz,A}\n\n{x=1679,m=44,a=2067,s=496}\n{x=2036,m=264,a=79,s=2244}";
+        assert_eq!(run(input), (1679 + 44 + 2067 + 496 + 2036 + 264 + 79 + 2244).to_string());
+    }
+}
\ No newline at end of file
@@ -1,5 +1,5 @@
 
-use crate::common::{self, Category, RuleCondition, RuleDestination, System, ACCEPT, REJECT};
+use crate::common::{self, System};
 
 pub fn run(input: &str) -> String {
     let (system, _) = common::parse_input(input);
@@ -7,104 +7,10 @@ pub fn run(input: &str) -> String {
     solutions.to_string()
 }
 
-#[derive(Debug, Clone)]
-struct Bounds {
-    low: [u32;4],
-    high: [u32; 4]
-}
-
-impl Bounds {
-    fn new() -> Self {
-        Self { low: [0;4], high: [4001;4] }
-    }
-
-    fn apply(mut self, condition: &RuleCondition) -> Self {
-        let index = Self::category_index(&condition.category);
-        if condition.lower_than_bound && self.high[index] > condition.bound{
-            self.high[index] = condition.bound
-        }
-        if !condition.lower_than_bound && self.low[index] < condition.bound {
-            self.low[index] = condition.bound
-        }
-        self
-    }
-
-    fn apply_not(mut self, condition: &RuleCondition) -> Self {
-        let index = Self::category_index(&condition.category);
-        // condition: cat < bound becomes cat >= bound or cat > bound - 1
-        if condition.lower_than_bound && self.low[index] < condition.bound + 1 {
-            self.low[index] = if condition.bound > 0 {
-                condition.bound - 1
-            } else {
-                0
-            }
-        }
-        // condition: cat > bound becomes cat <= bound or cat < bound + 1
-        if !condition.lower_than_bound && self.high[index] > condition.bound + 1{
-            self.high[index] = condition.bound + 1
-        }
-        self
-    }
-
-    fn category_index(category: &Category) -> usize {
-        match category {
-            Category::X => 0,
-            Category::M => 1,
-            Category::A => 2,
-            Category::S => 3,
-        }
-    }
-
-    fn count_solutions(&self) -> u64 {
-        self.low.iter().zip(self.high.iter()).map(|(&low, &high)|{
-            let cat_solutions = if low < high {
-                high - low - 1
-            } else {
-                0
-            };
-            cat_solutions as u64
-        }).product()
-    }
-}
-
 impl System {
     fn count_accepted_combinations(&self) -> u64 {
-        self.depth_first_search(&[], Bounds::new(), "in")
-    }
-
-    fn depth_first_search(&self, workflow_path: &[&str], bounds: Bounds, current_workflow_name: &str) -> u64 {
-        if workflow_path.contains(&current_workflow_name) {
-            return 0 // prevent cycles
-        }
-        let current_worflow = self.workflows.get(current_workflow_name).expect("Unknown workflow");
-        let mut total_solutions = 0;
-        let mut new_workflow_path = Vec::from(workflow_path);
-        new_workflow_path.push(current_workflow_name);
-        let mut bounds = bounds;
-        for rule in &current_worflow.rules {
-            let mut new_bounds = bounds.clone();
-            if let Some(condition) = &rule.condition {
-                new_bounds = new_bounds.apply(condition)
-            }
-            let rule_solutions = match &rule.destination {
-                RuleDestination::Workflow { name: rule_destination } => {
-                    self.depth_first_search(&new_workflow_path, new_bounds, &rule_destination)
-                },
-                RuleDestination::Decision(ACCEPT) => {
-                    // Reached accept leave, count solutions
-                    new_bounds.count_solutions()
-                },
-                RuleDestination::Decision(REJECT) => 0
-            };
-            total_solutions += rule_solutions;
-            if let Some(condition) = &rule.condition {
-                // moving on to the next rule is like applying the reverse of the rule
-                bounds = bounds.apply_not(condition)
-            }
-        }
-        total_solutions
+        self.compile().expect("Invalid workflows").count_accepted()
     }
-
 }
 
 #[cfg(test)]
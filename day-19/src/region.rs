@@ -0,0 +1,88 @@
+use crate::common::{Category, Part, RuleCondition};
+
+pub const MIN_RATING: u32 = 1;
+pub const MAX_RATING: u32 = 4000;
+
+/// The parts whose ratings are in a range for each category, the ranges being inclusive and indexed like `Category::ALL`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Region {
+    pub ranges: [(u32, u32); 4],
+}
+
+impl Category {
+    pub const ALL: [Category; 4] = [Category::X, Category::M, Category::A, Category::S];
+
+    pub fn index(&self) -> usize {
+        match self {
+            Category::X => 0,
+            Category::M => 1,
+            Category::A => 2,
+            Category::S => 3,
+        }
+    }
+}
+
+impl Region {
+    /// Every part with ratings from 1 to 4000
+    pub fn full() -> Self {
+        Self { ranges: [(MIN_RATING, MAX_RATING); 4] }
+    }
+
+    pub fn empty() -> Self {
+        Self { ranges: [(MAX_RATING, MIN_RATING); 4] }
+    }
+
+    pub fn range(&self, category: Category) -> (u32, u32) {
+        self.ranges[category.index()]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.iter().any(|&(low, high)| low > high)
+    }
+
+    /// The number of parts in the region
+    pub fn count(&self) -> u64 {
+        self.ranges.iter().map(|&(low, high)| if low > high { 0 } else { (high - low + 1) as u64 }).product()
+    }
+
+    pub fn contains(&self, part: &Part) -> bool {
+        Category::ALL.iter().all(|category| {
+            let (low, high) = self.range(*category);
+            (low..=high).contains(&part.get_value(category))
+        })
+    }
+
+    /// Splits the region into the parts matching the condition and the ones that don't
+    pub fn split(&self, condition: &RuleCondition) -> (Region, Region) {
+        let index = condition.category.index();
+        let (low, high) = self.ranges[index];
+        let (mut matching, mut others) = (*self, *self);
+        if condition.lower_than_bound {
+            matching.ranges[index] = (low, high.min(condition.bound.saturating_sub(1)));
+            others.ranges[index] = (low.max(condition.bound), high);
+        } else {
+            matching.ranges[index] = (low.max(condition.bound.saturating_add(1)), high);
+            others.ranges[index] = (low, high.min(condition.bound));
+        }
+        (matching, others)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split() {
+        let condition = RuleCondition { category: Category::M, lower_than_bound: true, bound: 1000 };
+        let (matching, others) = Region::full().split(&condition);
+        assert_eq!(matching.range(Category::M), (1, 999));
+        assert_eq!(others.range(Category::M), (1000, 4000));
+        assert_eq!(matching.count() + others.count(), Region::full().count());
+        let condition = RuleCondition { category: Category::M, lower_than_bound: false, bound: 4000 };
+        let (matching, others) = Region::full().split(&condition);
+        assert!(matching.is_empty());
+        assert_eq!(matching.count(), 0);
+        assert_eq!(others, Region::full());
+    }
+}
@@ -0,0 +1,183 @@
+use std::{collections::HashSet, fmt};
+
+use crate::common::{RuleDestination, System, Workflow};
+use crate::region::Region;
+
+/// A problem found in a system of workflows, naming the workflow and the index of the rule it is about
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Diagnostic {
+    /// There is no `in` workflow to start from
+    MissingStart,
+    UnknownDestination { workflow: String, rule: usize, destination: String },
+    /// The rule sends parts back to a workflow they already went through, `cycle` listing the workflows of the loop
+    Cycle { workflow: String, rule: usize, cycle: Vec<String> },
+    Unreachable { workflow: String },
+    /// The earlier rules of the workflow already take every part the rule would match
+    ShadowedRule { workflow: String, rule: usize },
+    /// The condition of the rule matches no part, whatever the earlier rules
+    EmptyCondition { workflow: String, rule: usize },
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diagnostic::MissingStart => write!(f, "No in workflow"),
+            Diagnostic::UnknownDestination { workflow, rule, destination } => {
+                write!(f, "{} rule {}: unknown destination {}", workflow, rule, destination)
+            }
+            Diagnostic::Cycle { workflow, rule, cycle } => write!(f, "{} rule {}: cycle {} -> {}", workflow, rule, cycle.join(" -> "), cycle[0]),
+            Diagnostic::Unreachable { workflow } => write!(f, "{}: unreachable from in", workflow),
+            Diagnostic::ShadowedRule { workflow, rule } => write!(f, "{} rule {}: shadowed by earlier rules", workflow, rule),
+            Diagnostic::EmptyCondition { workflow, rule } => write!(f, "{} rule {}: condition matches no part", workflow, rule),
+        }
+    }
+}
+
+impl Workflow {
+    /// For each rule, the parts of the region reaching it and the parts it sends to its destination
+    pub fn rule_regions(&self, region: Region) -> Vec<(Region, Region)> {
+        let mut remaining = region;
+        self.rules
+            .iter()
+            .map(|rule| {
+                let reaching = remaining;
+                let matching = match &rule.condition {
+                    Some(condition) => {
+                        let (matching, others) = reaching.split(condition);
+                        remaining = others;
+                        matching
+                    }
+                    None => {
+                        remaining = Region::empty();
+                        reaching
+                    }
+                };
+                (reaching, matching)
+            })
+            .collect()
+    }
+}
+
+impl System {
+    /// Looks for the problems making the system panic or loop, and for dead workflows and rules.
+    /// Cycles and unreachable workflows follow the parts from `in`, only taking the rules some of them reach.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let mut names: Vec<&str> = self.workflows.keys().map(|name| name.as_str()).collect();
+        names.sort();
+        for &name in &names {
+            let workflow = &self.workflows[name];
+            for (index, (rule, (_, matching))) in workflow.rules.iter().zip(workflow.rule_regions(Region::full())).enumerate() {
+                let always_empty = rule.condition.as_ref().is_some_and(|condition| Region::full().split(condition).0.is_empty());
+                if always_empty {
+                    diagnostics.push(Diagnostic::EmptyCondition { workflow: name.to_string(), rule: index });
+                } else if matching.is_empty() {
+                    diagnostics.push(Diagnostic::ShadowedRule { workflow: name.to_string(), rule: index });
+                }
+                if let RuleDestination::Workflow { name: destination } = &rule.destination {
+                    if !self.workflows.contains_key(destination) {
+                        diagnostics.push(Diagnostic::UnknownDestination { workflow: name.to_string(), rule: index, destination: destination.clone() });
+                    }
+                }
+            }
+        }
+        if !self.workflows.contains_key("in") {
+            diagnostics.push(Diagnostic::MissingStart);
+            return diagnostics;
+        }
+        let mut reached = HashSet::new();
+        let mut cycles = Vec::new();
+        self.follow("in", Region::full(), &mut Vec::new(), &mut reached, &mut cycles);
+        diagnostics.extend(cycles);
+        let unreachable = names.iter().filter(|name| !reached.contains(*name));
+        diagnostics.extend(unreachable.map(|name| Diagnostic::Unreachable { workflow: name.to_string() }));
+        diagnostics
+    }
+
+    /// Follows the parts of the region entering a workflow, reporting every rule sending some of them
+    /// back to a workflow of the current path
+    fn follow<'a>(&'a self, name: &'a str, region: Region, path: &mut Vec<&'a str>, reached: &mut HashSet<&'a str>, cycles: &mut Vec<Diagnostic>) {
+        let Some(workflow) = self.workflows.get(name) else {
+            return; // Already reported as an unknown destination
+        };
+        reached.insert(name);
+        path.push(name);
+        for (index, (rule, (_, matching))) in workflow.rules.iter().zip(workflow.rule_regions(region)).enumerate() {
+            let RuleDestination::Workflow { name: destination } = &rule.destination else {
+                continue;
+            };
+            if matching.is_empty() {
+                continue;
+            }
+            if let Some(position) = path.iter().position(|workflow| workflow == destination) {
+                let cycle = Diagnostic::Cycle { workflow: name.to_string(), rule: index, cycle: path[position..].iter().map(|workflow| workflow.to_string()).collect() };
+                if !cycles.contains(&cycle) {
+                    cycles.push(cycle);
+                }
+            } else {
+                self.follow(destination, matching, path, reached, cycles);
+            }
+        }
+        path.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_system() {
+        let system = System::parse("px{a<2006:qkq,m>2090:A,rfg}
+pv{a>1716:R,A}
+lnx{m>1548:A,A}
+rfg{s<537:gd,x>2440:R,A}
+qs{s>3448:A,lnx}
+qkq{x<1416:A,crn}
+crn{x>2662:A,R}
+in{s<1351:px,qqz}
+qqz{s>2770:qs,m<1801:hdj,R}
+gd{a>3333:R,R}
+hdj{m>838:A,pv}");
+        assert_eq!(system.validate(), vec![]);
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let system = System::parse("in{x<10:a,x<5:R,x>4000:A,b}
+a{s<1:R,in}
+b{m>1:zz,A}
+c{A}");
+        let diagnostics = system.validate();
+        assert_eq!(
+            diagnostics,
+            vec![
+                Diagnostic::EmptyCondition { workflow: "a".to_string(), rule: 0 },
+                Diagnostic::UnknownDestination { workflow: "b".to_string(), rule: 0, destination: "zz".to_string() },
+                Diagnostic::ShadowedRule { workflow: "in".to_string(), rule: 1 },
+                Diagnostic::EmptyCondition { workflow: "in".to_string(), rule: 2 },
+                Diagnostic::Cycle { workflow: "a".to_string(), rule: 1, cycle: vec!["in".to_string(), "a".to_string()] },
+                Diagnostic::Unreachable { workflow: "c".to_string() },
+            ]
+        );
+        assert_eq!(diagnostics[4].to_string(), "a rule 1: cycle in -> a -> in");
+        assert_eq!(diagnostics[2].to_string(), "in rule 1: shadowed by earlier rules");
+    }
+
+    #[test]
+    fn test_missing_start_and_dead_cycle() {
+        let system = System::parse("a{x>4000:a,b}\nb{A,a}");
+        assert_eq!(
+            system.validate(),
+            vec![Diagnostic::EmptyCondition { workflow: "a".to_string(), rule: 0 }, Diagnostic::ShadowedRule { workflow: "b".to_string(), rule: 1 }, Diagnostic::MissingStart]
+        );
+    }
+
+    #[test]
+    fn test_cycle_needs_parts_to_loop() {
+        let system = System::parse("in{x<10:a,A}\na{x>100:in,R}");
+        assert_eq!(system.validate(), vec![]);
+        let system = System::parse("in{x<10:a,A}\na{x>5:in,R}");
+        assert_eq!(system.validate(), vec![Diagnostic::Cycle { workflow: "a".to_string(), rule: 0, cycle: vec!["in".to_string(), "a".to_string()] }]);
+    }
+}